@@ -1,17 +1,13 @@
 import os
-import copy
-import time
-from datetime import datetime
-import pytz
 from time_trigger_task import task_io
 
 # === 配置区域 ===
 CONFIG_DIR = "configs"
+STATE_FILE = task_io.DEFAULT_STATE_PATH
+HISTORY_FILE = task_io.DEFAULT_HISTORY_PATH
 TOLERANCE_MINUTES = 30
-TIME_FORMAT = "%Y-%m-%d %H:%M:%S"
 ENV_KEY_NAME = "DEVICE_KEYS"
-MAX_RETRIES = 3
-RETRY_DELAY = 2
+REQUEST_TIMEOUT = 20
 
 
 def load_secret_keys():
@@ -27,15 +23,6 @@ def load_secret_keys():
         return []
 
 
-def get_current_time(tz_name="Asia/Shanghai"):
-    try:
-        tz = pytz.timezone(tz_name)
-        return datetime.now(tz)
-    except Exception:
-        print(f"⚠️ 时区 '{tz_name}' 无效，使用 UTC")
-        return datetime.now(pytz.utc)
-
-
 def process_tasks():
     secret_keys = load_secret_keys()
     # ✅ 调用 Rust: 极速扫描文件列表
@@ -44,53 +31,55 @@ def process_tasks():
     if not config_files:
         print("💤 没有找到配置文件。")
         return
+
+    # ✅ 调用 Rust: 运行前先整体校验配置, 提前暴露手误
+    for diag in task_io.validate_dir(CONFIG_DIR):
+        icon = "❌" if diag.severity == "error" else "⚠️"
+        print(f"{icon} 配置校验: {diag}")
     files_changed = False
     for config_file in config_files:
         print(f"\n📄 检查任务: {config_file}")
         try:
-            # ✅ 调用 Rust: 安全读取并解析 JSON
-            data = task_io.read_config(config_file)
+            # ✅ 调用 Rust: 读取并解析为类型化的 TaskConfig (字段错误会带路径报出)
+            task = task_io.TaskConfig.load(config_file)
         except Exception as e:
             print(f"   ❌ (Rust内核) 读取失败: {e}")
             continue
-        if data.get("executed") is True:
+        # ✅ 调用 Rust: 时区换算与时间窗口判断
+        due = task_io.evaluate_due(
+            task, tolerance_minutes=TOLERANCE_MINUTES, state_path=STATE_FILE)
+        if due.skipped:
+            # ✅ 调用 Rust: 按 misfire_policy 放弃的触发点记录到状态文件
+            if task_io.record_skipped(STATE_FILE, task, due):
+                print(f"   ⚠️ 已放弃 {len(due.skipped)} 个错过的触发点 (misfire_policy: {task.misfire_policy})")
+                files_changed = True
+        if due.state == task_io.DueState.AlreadyExecuted:
             print("   ⏭️ 跳过: 任务已标记为已执行")
             continue
-
-        trigger_time_str = data.get("trigger_time")
-        tz_name = data.get("timezone", "Asia/Shanghai")
-        if not trigger_time_str:
-            continue
-        try:
-            target_tz = pytz.timezone(tz_name)
-            naive_trigger_time = datetime.strptime(
-                trigger_time_str, TIME_FORMAT)
-            trigger_time = target_tz.localize(naive_trigger_time)
-            current_time = get_current_time(tz_name)
-        except ValueError as e:
-            print(f"   ❌ 时间格式错误: {e}")
+        if due.state == task_io.DueState.Invalid:
+            print(f"   ❌ 时间无效: {due.message}")
             continue
-        diff = current_time - trigger_time
-        diff_minutes = diff.total_seconds() / 60
-        print(
-            f"   ⏳ 设定: {trigger_time} | 当前: {current_time.strftime('%H:%M:%S')}")
-        print(f"   ⏳ 延迟: {diff_minutes:.1f} 分钟")
-        if 0 <= diff_minutes <= TOLERANCE_MINUTES:
+
+        print(f"   ⏳ 设定: {due.trigger_time} | 当前: {due.now}")
+        print(f"   ⏳ 延迟: {due.delay_minutes:.1f} 分钟")
+        if due.state == task_io.DueState.Due:
             print("   🚀 准备执行...")
 
-            url = data.get("webhook_url")
-            method = data.get("method", "POST").upper()
-            payload = copy.deepcopy(data.get("body", {}))
+            url = task.webhook_url
+            method = task.method.upper()
+            payload = task.body
+            # 只有 json 的对象 body 注入 Key; form / multipart / raw 原样发送
+            inject = task.body_type == "json" and isinstance(payload, dict)
 
-            if "device_keys" not in payload:
+            if inject and "device_keys" not in payload:
                 payload["device_keys"] = []
 
             # --- 注入 Key 逻辑 (保持 Python 处理灵活性) ---
-            if isinstance(secret_keys, list) and secret_keys:
+            if inject and isinstance(secret_keys, list) and secret_keys:
                 print(f"      注入 {len(secret_keys)} 个 Keys (追加模式)")
                 payload["device_keys"] = list(
                     set(payload["device_keys"] + secret_keys))
-            elif isinstance(secret_keys, dict):
+            elif inject and isinstance(secret_keys, dict):
                 original_list = payload["device_keys"]
                 resolved_list = []
                 if not original_list and secret_keys:
@@ -106,55 +95,49 @@ def process_tasks():
                 payload["device_keys"] = resolved_list
 
             # --- 发送请求 (替换为 Rust 绑定) ---
-            success = False
-            for attempt in range(1, MAX_RETRIES + 1):
-                try:
-                    print(
-                        f"      📡 (Rust内核) 发送请求... (尝试 {attempt}/{MAX_RETRIES})")
-
-                    # ✅ 调用 Rust: 发送 HTTP 请求
-                    # 参数: method, url, payload, timeout(秒)
-                    # 返回: (status_code, body_text)
-                    status_code, resp_text = task_io.send_request(
-                        method,
-                        url,
-                        payload,
-                        20  # timeout
-                    )
-
-                    if 200 <= status_code < 300:
-                        print(f"   ✅ 发送成功! 状态码: {status_code}")
-                        success = True
-                        break
-                    else:
-                        print(f"   ⚠️ 失败: 服务器返回 {status_code}")
-                        # 可选: 打印返回内容帮助调试
-                        # print(f"      响应: {resp_text[:100]}...")
-
-                except Exception as req_err:
-                    # Rust 抛出的 PyConnectionError 等异常会在这里被捕获
-                    print(f"   ❌ (Rust内核) 网络异常: {req_err}")
-
-                if attempt < MAX_RETRIES:
-                    time.sleep(RETRY_DELAY)
+            # ✅ 调用 Rust: 按任务的 retry 策略发送 (指数退避 + 抖动, 遵循 Retry-After)
+            # 每次尝试 (含失败) 都会由 Rust 追加到执行历史中, 返回全部尝试记录
+            print("      📡 (Rust内核) 发送请求...")
+            try:
+                attempts = task_io.send_with_retry(
+                    method,
+                    url,
+                    payload,
+                    REQUEST_TIMEOUT,
+                    journal=HISTORY_FILE,
+                    task=task,
+                )
+            except Exception as req_err:
+                # 配置错误 (如 retry 格式错误) 会以异常抛出
+                print(f"   ❌ (Rust内核) 请求失败: {req_err}")
+                attempts = []
+            for record in attempts:
+                if record.status is not None and record.error:
+                    # 收到响应但不满足 expect 断言
+                    print(f"      尝试 {record.attempt}: 状态码 {record.status}, {record.error}")
+                elif record.status is not None:
+                    print(f"      尝试 {record.attempt}: 状态码 {record.status}")
+                else:
+                    print(f"      尝试 {record.attempt}: 网络异常 {record.error}")
+                if record.journal_error:
+                    print(f"      ⚠️ 警告: {record.journal_error}")
+            success = bool(attempts) and attempts[-1].ok
 
             if success:
-                data["executed"] = True
-                data["executed_at"] = current_time.strftime(TIME_FORMAT)
+                print(f"   ✅ 发送成功! 状态码: {attempts[-1].status}")
                 try:
-                    # ✅ 调用 Rust: 将更新后的数据写回磁盘
-                    task_io.save_config(config_file, data)
-                    print("   💾 状态已更新并保存 (Rust内核)")
+                    # ✅ 调用 Rust: 执行状态写入独立的状态文件, 不再改动 configs/
+                    task_io.mark_executed(STATE_FILE, task, due)
+                    print(f"   💾 状态已记录到 {STATE_FILE} (Rust内核)")
                     files_changed = True
                 except Exception as e:
                     print(f"   ❌ (Rust内核) 保存失败: {e}")
             else:
                 print(f"   ⛔️ 最终失败")
+        elif due.state == task_io.DueState.Pending:
+            print("   zzz 时间未到")
         else:
-            if diff_minutes < 0:
-                print("   zzz 时间未到")
-            else:
-                print(f"   🚫 已过期 (超过 {TOLERANCE_MINUTES} 分钟)")
+            print(f"   🚫 已过期 (超过 {TOLERANCE_MINUTES} 分钟)")
     if files_changed:
         print("\n🏁 有状态更新。")
     else:
@@ -0,0 +1,232 @@
+use pyo3::prelude::*;
+use std::sync::{Arc, Mutex, OnceLock};
+
+// 执行异步请求的 Tokio 运行时; 请求本身是阻塞的, 放在它的阻塞线程池中执行
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("task-io")
+            .build()
+            .expect("初始化 Tokio 运行时失败")
+    })
+}
+
+type Outcome = Box<dyn FnOnce(Python) -> PyResult<PyObject> + Send>;
+
+/// 工作线程与事件循环共享的结果槽位; 为空表示 work 没有正常返回
+type Slot = Arc<Mutex<Option<Outcome>>>;
+
+/// 在 Tokio 线程池中执行 `work` (不持有 GIL), 返回可 await 的 asyncio.Future
+///
+/// 必须在 asyncio 事件循环中调用。工作线程不接触任何 Python 对象:
+/// 结果放入共享的槽位后关闭 socketpair 的一端, 事件循环在 sock_recv 返回后取出结果。
+/// 若由工作线程获取 GIL 回调事件循环, 解释器退出时它可能被强制结束而导致进程崩溃。
+pub(crate) fn spawn_awaitable<'py, T, E, F>(py: Python<'py>, work: F) -> PyResult<&'py PyAny>
+where
+    T: IntoPy<PyObject> + Send + 'static,
+    E: Into<PyErr> + Send + 'static,
+    F: FnOnce() -> Result<T, E> + Send + 'static,
+{
+    let asyncio = py.import("asyncio")?;
+    let event_loop = asyncio.call_method0("get_running_loop")?;
+    let future = event_loop.call_method0("create_future")?;
+    let (reader, writer): (&PyAny, &PyAny) =
+        py.import("socket")?.call_method0("socketpair")?.extract()?;
+    reader.call_method1("setblocking", (false,))?;
+    let notify = Notify::from_socket(writer.call_method0("detach")?.extract()?);
+
+    let slot: Slot = Arc::default();
+    let filled = slot.clone();
+    runtime().spawn_blocking(move || complete(work, &filled, notify));
+
+    let waiting = asyncio.call_method1(
+        "ensure_future",
+        (event_loop.call_method1("sock_recv", (reader, 1))?,),
+    )?;
+    waiting.call_method1(
+        "add_done_callback",
+        (Completer {
+            future: future.into(),
+            reader: reader.into(),
+            slot,
+        },),
+    )?;
+    Ok(future)
+}
+
+// 在工作线程中执行 work, 结果放入槽位后关闭写端唤醒事件循环
+//
+// work panic 时槽位保持为空, 写端在栈展开时同样会关闭, 事件循环据此报告 RuntimeError。
+fn complete<T, E, F>(work: F, slot: &Slot, notify: Notify)
+where
+    T: IntoPy<PyObject> + Send + 'static,
+    E: Into<PyErr> + Send + 'static,
+    F: FnOnce() -> Result<T, E>,
+{
+    let result = work();
+    let outcome: Outcome = Box::new(move |py| result.map(|v| v.into_py(py)).map_err(Into::into));
+    *slot.lock().unwrap() = Some(outcome);
+    drop(notify);
+}
+
+// socketpair 的写端, drop 时关闭
+struct Notify {
+    #[cfg(unix)]
+    _socket: std::os::unix::net::UnixStream,
+    #[cfg(windows)]
+    _socket: std::net::TcpStream,
+}
+
+impl Notify {
+    #[cfg(unix)]
+    fn from_socket(fd: i32) -> Self {
+        use std::os::unix::io::FromRawFd;
+        // SAFETY: fd 来自 socket.detach(), 所有权已转移给这里
+        Notify {
+            _socket: unsafe { std::os::unix::net::UnixStream::from_raw_fd(fd) },
+        }
+    }
+
+    #[cfg(windows)]
+    fn from_socket(handle: u64) -> Self {
+        use std::os::windows::io::FromRawSocket;
+        // SAFETY: 句柄来自 socket.detach(), 所有权已转移给这里
+        Notify {
+            _socket: unsafe { std::net::TcpStream::from_raw_socket(handle) },
+        }
+    }
+}
+
+// 在事件循环线程中设置 Future 的结果; Future 已被取消时只关闭读端
+#[pyclass]
+struct Completer {
+    future: PyObject,
+    reader: PyObject,
+    slot: Slot,
+}
+
+#[pymethods]
+impl Completer {
+    fn __call__(&self, py: Python, _waiting: &PyAny) -> PyResult<()> {
+        self.reader.call_method0(py, "close")?;
+        let future = self.future.as_ref(py);
+        if future.call_method0("done")?.is_true()? {
+            return Ok(());
+        }
+        let outcome = self.slot.lock().unwrap().take();
+        let error = match outcome.map(|outcome| outcome(py)) {
+            Some(Ok(value)) => {
+                future.call_method1("set_result", (value,))?;
+                return Ok(());
+            }
+            Some(Err(e)) => e,
+            None => PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("发送线程异常退出"),
+        };
+        future.call_method1("set_exception", (error.value(py),))?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    // 在新线程中执行 complete, 返回槽位、读端与线程句柄
+    fn spawn(
+        work: impl FnOnce() -> PyResult<u32> + Send + 'static,
+    ) -> (Slot, UnixStream, std::thread::JoinHandle<()>) {
+        let (reader, writer) = UnixStream::pair().unwrap();
+        reader
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let slot: Slot = Arc::default();
+        let filled = slot.clone();
+        let handle =
+            std::thread::spawn(move || complete(work, &filled, Notify { _socket: writer }));
+        (slot, reader, handle)
+    }
+
+    // 事件循环的 sock_recv 收到 EOF 即返回
+    fn wait_closed(mut reader: UnixStream) {
+        let mut buf = [0; 1];
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn fills_slot_before_closing() {
+        let (slot, reader, handle) = spawn(|| Ok(1));
+        wait_closed(reader);
+        assert!(slot.lock().unwrap().is_some());
+        handle.join().unwrap();
+
+        let (slot, reader, handle) =
+            spawn(|| Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("失败")));
+        wait_closed(reader);
+        assert!(slot.lock().unwrap().is_some());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn panic_closes_with_empty_slot() {
+        let (slot, reader, handle) = spawn(|| panic!("work panicked"));
+        wait_closed(reader);
+        assert!(handle.join().is_err());
+        // 槽位为空, Completer 会设置 RuntimeError
+        assert!(slot.lock().unwrap().is_none());
+    }
+
+    // 以给定的槽位调用 Completer, 返回 Future 的结果或异常
+    fn settle(slot: Slot) -> Result<u32, String> {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let event_loop = py.import("asyncio")?.call_method0("new_event_loop")?;
+            let future = event_loop.call_method0("create_future")?;
+            let (reader, _writer): (&PyAny, &PyAny) =
+                py.import("socket")?.call_method0("socketpair")?.extract()?;
+            let completer = Completer {
+                future: future.into(),
+                reader: reader.into(),
+                slot,
+            };
+            completer.__call__(py, py.None().as_ref(py))?;
+            event_loop.call_method0("close")?;
+            assert!(reader.getattr("_closed")?.is_true()?);
+            Ok::<_, PyErr>(match future.call_method0("exception")? {
+                e if e.is_none() => Ok(future.call_method0("result")?.extract()?),
+                e => Err(format!("{}: {}", e.get_type().name()?, e)),
+            })
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn completer_sets_result_or_error() {
+        let (slot, reader, handle) = spawn(|| Ok(7));
+        wait_closed(reader);
+        handle.join().unwrap();
+        assert_eq!(settle(slot), Ok(7));
+
+        let (slot, reader, handle) = spawn(|| panic!("work panicked"));
+        wait_closed(reader);
+        assert!(handle.join().is_err());
+        assert_eq!(
+            settle(slot),
+            Err("RuntimeError: 发送线程异常退出".to_string())
+        );
+    }
+
+    #[test]
+    fn reader_closed_first() {
+        // Future 被取消时读端先关闭, 工作线程照常结束
+        let (reader, writer) = UnixStream::pair().unwrap();
+        drop(reader);
+        let slot: Slot = Arc::default();
+        complete(|| Ok::<_, PyErr>(1), &slot, Notify { _socket: writer });
+        assert!(slot.lock().unwrap().is_some());
+    }
+}
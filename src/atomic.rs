@@ -0,0 +1,168 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 备份文件路径: `Episode_01.json` -> `Episode_01.json.bak`
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// 原子地写入文件
+///
+/// 先写到同目录下的临时文件并 fsync, 再 rename 覆盖目标, 进程在任何时刻被杀
+/// 都只会留下旧文件或新文件, 不会出现写了一半的文件。临时文件名不以 `.json`
+/// 结尾, 不会被 list_configs 扫到, 且每次调用都不同, 多个线程可以同时写同一个文件。
+/// `keep_backup` 为 true 时先以同样的方式把旧文件内容写入 `.bak`。
+pub fn write_atomic(path: &Path, content: &[u8], keep_backup: bool) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new("."),
+    };
+    if path.file_name().is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "路径缺少文件名",
+        ));
+    }
+
+    if keep_backup {
+        match fs::read(path) {
+            Ok(old) => replace(dir, &backup_path(path), &old)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+    replace(dir, path, content)?;
+
+    // rename 本身也要落盘; 部分平台不支持对目录 fsync, 忽略错误
+    if let Ok(d) = File::open(dir) {
+        let _ = d.sync_all();
+    }
+    Ok(())
+}
+
+// 写入唯一的临时文件后 rename 覆盖 path
+fn replace(dir: &Path, path: &Path, content: &[u8]) -> io::Result<()> {
+    let (tmp_path, mut file) = create_temp(dir, path)?;
+    let result = (|| {
+        file.write_all(content)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&tmp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+// 临时文件名: `.{文件名}.tmp-{pid}-{序号}`, 序号在进程内递增; 以 create_new 打开, 已存在时换下一个
+fn create_temp(dir: &Path, path: &Path) -> io::Result<(PathBuf, File)> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let file_name = path.file_name().unwrap_or_default();
+    loop {
+        let mut tmp_name = std::ffi::OsString::from(".");
+        tmp_name.push(file_name);
+        tmp_name.push(format!(
+            ".tmp-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let tmp_path = dir.join(tmp_name);
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp_path)
+        {
+            Ok(file) => return Ok((tmp_path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 每个测试独立的临时目录
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("task_io_atomic_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn writes_new_file_without_leftovers() {
+        let dir = temp_dir("new");
+        let path = dir.join("a.json");
+        write_atomic(&path, b"{}", false).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"{}");
+        assert_eq!(entries(&dir), ["a.json"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn replaces_existing_file() {
+        let dir = temp_dir("replace");
+        let path = dir.join("a.json");
+        fs::write(&path, "old").unwrap();
+        write_atomic(&path, b"new", false).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(entries(&dir), ["a.json"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_backup_of_previous_content() {
+        let dir = temp_dir("backup");
+        let path = dir.join("a.json");
+        // 原文件不存在时不生成备份
+        write_atomic(&path, b"v1", true).unwrap();
+        assert_eq!(entries(&dir), ["a.json"]);
+        write_atomic(&path, b"v2", true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "v2");
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), "v1");
+        assert_eq!(entries(&dir), ["a.json", "a.json.bak"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn concurrent_writers_do_not_collide() {
+        let dir = temp_dir("concurrent");
+        let path = dir.join("a.json");
+        std::thread::scope(|s| {
+            for t in 0..8 {
+                let path = &path;
+                s.spawn(move || {
+                    for i in 0..50 {
+                        let content = format!("{}-{}", t, i);
+                        write_atomic(path, content.as_bytes(), true).unwrap();
+                    }
+                });
+            }
+        });
+        // 最终内容与备份都是某一次完整的写入
+        for file in [path.clone(), backup_path(&path)] {
+            let content = fs::read_to_string(&file).unwrap();
+            let (t, i) = content.split_once('-').unwrap();
+            assert!(t.parse::<u32>().unwrap() < 8 && i.parse::<u32>().unwrap() < 50);
+        }
+        assert_eq!(entries(&dir), ["a.json", "a.json.bak"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
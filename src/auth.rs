@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 请求认证方式 (任务配置中的 `auth` 字段)
+///
+/// ```json
+/// "auth": { "type": "bearer", "token": "${WEBHOOK_TOKEN}" }
+/// "auth": { "type": "basic", "username": "bot", "password": "${BOT_PASSWORD}" }
+/// "auth": { "type": "api_key", "name": "X-API-Key", "value": "${API_KEY}", "in": "header" }
+/// ```
+///
+/// 字段值中的 `${NAME}` 在发送前替换为环境变量, 密钥只放在 Secrets 中而不写进 configs/。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Auth {
+    Bearer {
+        token: String,
+    },
+    Basic {
+        username: String,
+        #[serde(default)]
+        password: String,
+    },
+    ApiKey {
+        name: String,
+        value: String,
+        #[serde(rename = "in", default)]
+        location: KeyLocation,
+    },
+}
+
+/// API Key 放在请求头还是查询参数中
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyLocation {
+    #[default]
+    Header,
+    Query,
+}
+
+impl Auth {
+    /// 替换各字段中的 `${NAME}`
+    pub fn resolve(&self) -> Result<Auth, String> {
+        Ok(match self {
+            Auth::Bearer { token } => Auth::Bearer {
+                token: resolve_env(token)?,
+            },
+            Auth::Basic { username, password } => Auth::Basic {
+                username: resolve_env(username)?,
+                password: resolve_env(password)?,
+            },
+            Auth::ApiKey {
+                name,
+                value,
+                location,
+            } => Auth::ApiKey {
+                name: resolve_env(name)?,
+                value: resolve_env(value)?,
+                location: *location,
+            },
+        })
+    }
+
+    /// 写在配置中的密钥字段 (字段名, 值), 用于提醒不要写明文
+    pub fn secrets(&self) -> Vec<(&'static str, &str)> {
+        match self {
+            Auth::Bearer { token } => vec![("token", token)],
+            Auth::Basic { password, .. } => vec![("password", password)],
+            Auth::ApiKey { value, .. } => vec![("value", value)],
+        }
+    }
+
+    pub fn apply(
+        &self,
+        builder: reqwest::blocking::RequestBuilder,
+    ) -> reqwest::blocking::RequestBuilder {
+        match self {
+            Auth::Bearer { token } => builder.bearer_auth(token),
+            Auth::Basic { username, password } => builder.basic_auth(username, Some(password)),
+            Auth::ApiKey {
+                name,
+                value,
+                location: KeyLocation::Header,
+            } => builder.header(name.as_str(), value.as_str()),
+            Auth::ApiKey {
+                name,
+                value,
+                location: KeyLocation::Query,
+            } => builder.query(&[(name, value)]),
+        }
+    }
+}
+
+/// 是否引用了环境变量
+pub fn is_env_ref(s: &str) -> bool {
+    s.contains("${")
+}
+
+/// 把字符串中的 `${NAME}` 替换为环境变量的值, 变量未设置时报错
+pub fn resolve_env(s: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| format!("环境变量引用缺少 '}}': {}", s))?;
+        let name = &after[..end];
+        let value = std::env::var(name).map_err(|_| format!("环境变量 {} 未设置", name))?;
+        out.push_str(&value);
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// 替换请求头中的 `${NAME}`
+pub fn resolve_headers(
+    headers: &BTreeMap<String, String>,
+) -> Result<Vec<(String, String)>, String> {
+    headers
+        .iter()
+        .map(|(k, v)| Ok((k.clone(), resolve_env(v)?)))
+        .collect()
+}
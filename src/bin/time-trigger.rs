@@ -0,0 +1,276 @@
+//! 独立的调度器命令行, 与 python/time_trigger_task 的流程相同, 但不需要 Python 环境
+
+use chrono::{Duration, Utc};
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use task_io::{
+    evaluate, load_config, load_secret_keys, run_once, scan_configs, validate_directory, Change,
+    ClientOptions, Daemon, DaemonEvent, DueState, HttpClient, RetryPolicy, RunOptions, StateStore,
+    TaskReport, DEFAULT_HISTORY_PATH, DEFAULT_STATE_PATH, ENV_KEY_NAME,
+};
+
+#[derive(Parser)]
+#[command(name = "time-trigger", version, about = "定时触发 Webhook 任务")]
+struct Cli {
+    /// 配置目录
+    #[arg(long, global = true, default_value = "configs")]
+    dir: String,
+    /// 执行状态文件
+    #[arg(long, global = true, default_value = DEFAULT_STATE_PATH)]
+    state: PathBuf,
+    /// 容忍窗口 (分钟)
+    #[arg(long, global = true, default_value_t = 30)]
+    tolerance: i64,
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// run 与 daemon 共用的发送参数
+#[derive(Args)]
+struct FireArgs {
+    /// 执行历史文件
+    #[arg(long, default_value = DEFAULT_HISTORY_PATH)]
+    history: PathBuf,
+    /// 请求超时 (秒)
+    #[arg(long, default_value_t = 20)]
+    timeout: u64,
+    /// 最大尝试次数 (任务配置了 retry 时以任务为准)
+    #[arg(long, default_value_t = 3)]
+    retries: u32,
+    /// 代理地址 (默认沿用 HTTP(S)_PROXY 环境变量)
+    #[arg(long)]
+    proxy: Option<String>,
+    /// 同时处理的任务数 (daemon 按触发时间逐个处理)
+    #[arg(long, default_value_t = 4)]
+    concurrency: usize,
+    /// 每个主机每秒最多发起的请求数 (含重试)
+    #[arg(long)]
+    rate_limit: Option<f64>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 执行一轮调度: 触发到期的任务并记录状态
+    Run(FireArgs),
+    /// 常驻运行: 休眠到每个任务的触发时间并立即触发
+    Daemon(FireArgs),
+    /// 列出所有任务及当前状态
+    List,
+    /// 校验配置, 有错误时退出码为 1
+    Validate,
+    /// 显示每个任务的下一次触发时间
+    Next,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match &cli.command {
+        Command::Run(args) => cmd_run(&cli, args),
+        Command::Daemon(args) => cmd_daemon(&cli, args),
+        Command::List | Command::Next => cmd_list(&cli),
+        Command::Validate => cmd_validate(&cli.dir),
+    }
+}
+
+fn run_options(cli: &Cli, args: &FireArgs) -> Result<RunOptions, String> {
+    let secret_keys = match load_secret_keys(ENV_KEY_NAME) {
+        Ok(keys) => keys,
+        Err(e) => {
+            eprintln!("⚠️ 警告: {}", e);
+            serde_json::Value::Array(Vec::new())
+        }
+    };
+    // 一轮调度中的所有任务与重试共用一个 Client
+    let client = HttpClient::new(ClientOptions {
+        proxy: args.proxy.clone(),
+        host_rate_limit: args.rate_limit,
+        ..ClientOptions::default()
+    })
+    .map_err(|e| e.to_string())?;
+    Ok(RunOptions {
+        config_dir: cli.dir.clone(),
+        state_path: cli.state.clone(),
+        history_path: Some(args.history.clone()),
+        tolerance: Duration::minutes(cli.tolerance),
+        timeout_secs: args.timeout,
+        retry: RetryPolicy {
+            max_attempts: args.retries,
+            ..RetryPolicy::default()
+        },
+        secret_keys,
+        client,
+        concurrency: args.concurrency,
+    })
+}
+
+fn print_diagnostics(dir: &str) {
+    for diag in validate_directory(dir) {
+        let icon = if diag.is_error() { "❌" } else { "⚠️" };
+        println!("{} 配置校验: {}", icon, diag);
+    }
+}
+
+fn cmd_run(cli: &Cli, args: &FireArgs) -> ExitCode {
+    let opts = match run_options(cli, args) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    print_diagnostics(&cli.dir);
+    match run_once(&opts, Utc::now(), print_report) {
+        Ok(reports) => {
+            let failed = reports
+                .iter()
+                .any(|r| r.outcome.as_ref().is_some_and(|o| !o.success));
+            if failed {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn cmd_daemon(cli: &Cli, args: &FireArgs) -> ExitCode {
+    let opts = match run_options(cli, args) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    print_diagnostics(&cli.dir);
+    let (mut daemon, errors) = match Daemon::new(opts, Utc::now()) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    errors.iter().for_each(print_report);
+    let (total, pending) = daemon.counts();
+    println!(
+        "🕰️ 常驻模式: 已加载 {} 个任务, 其中 {} 个待触发",
+        total, pending
+    );
+    if let Err(e) = daemon.watch() {
+        eprintln!("⚠️ 警告: {}, 修改配置后需要重启", e);
+    }
+    daemon.run(print_event)
+}
+
+fn print_event(event: &DaemonEvent) {
+    match event {
+        DaemonEvent::Fired(report) => print_report(report),
+        DaemonEvent::Reloaded { file, change } => match change {
+            Change::Added => println!("\n➕ 新增任务: {}", file),
+            Change::Updated => println!("\n🔄 已重新加载: {}", file),
+            Change::Removed => println!("\n➖ 已移除任务: {}", file),
+            Change::Invalid(e) => println!("\n❌ 重新加载失败, 沿用之前的版本: {}", e),
+        },
+        DaemonEvent::Warning(e) => eprintln!("\n⚠️ 警告: {}", e),
+    }
+}
+
+fn print_report(report: &TaskReport) {
+    println!("\n📄 检查任务: {}", report.file);
+    let (Some(due), Some(_)) = (&report.due, &report.config) else {
+        if let Some(e) = &report.error {
+            println!("   ❌ 读取失败: {}", e);
+        }
+        return;
+    };
+    match due.state {
+        DueState::AlreadyExecuted => println!("   ⏭️ 跳过: 任务已标记为已执行"),
+        DueState::Invalid => println!(
+            "   ❌ 时间无效: {}",
+            due.message.as_deref().unwrap_or_default()
+        ),
+        DueState::Pending => println!("   zzz 时间未到"),
+        DueState::Expired => println!("   🚫 已过期"),
+        DueState::Due => {}
+    }
+    if let Some(outcome) = &report.outcome {
+        for attempt in &outcome.attempts {
+            match (attempt.status, &attempt.error) {
+                // 收到响应但不满足 expect 断言
+                (Some(status), Some(e)) => println!(
+                    "      📡 尝试 {}: 状态码 {} ({} ms), {}",
+                    attempt.attempt, status, attempt.latency_ms, e
+                ),
+                (Some(status), None) => println!(
+                    "      📡 尝试 {}: 状态码 {} ({} ms)",
+                    attempt.attempt, status, attempt.latency_ms
+                ),
+                (None, Some(e)) => println!("      📡 尝试 {}: {}", attempt.attempt, e),
+                (None, None) => {}
+            }
+            if let Some(e) = &attempt.journal_error {
+                eprintln!("      ⚠️ 警告: {}", e);
+            }
+        }
+        if outcome.success {
+            println!("   ✅ 发送成功");
+        } else {
+            println!("   ⛔️ 最终失败");
+        }
+    }
+    if let Some(e) = &report.error {
+        println!("   ❌ {}", e);
+    }
+}
+
+// list 与 next 共用: 读取一次状态, 逐个任务计算当前状态与下一次触发时间
+fn cmd_list(cli: &Cli) -> ExitCode {
+    let tolerance = Duration::minutes(cli.tolerance);
+    let store = match StateStore::load(&cli.state) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("❌ 读取状态失败 {}: {}", cli.state.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let now = Utc::now();
+    let next_only = matches!(cli.command, Command::Next);
+    for file in scan_configs(&cli.dir) {
+        let config = match load_config(&file) {
+            Ok(c) => c,
+            Err(e) => {
+                println!("{}\t❌ {}", file, e);
+                continue;
+            }
+        };
+        let due = evaluate(&config, &store, now, tolerance);
+        let name = config.task_name.as_deref().unwrap_or("-");
+        let next = due
+            .next_fire()
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S%:z").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        if next_only {
+            println!("{}\t{}", next, name);
+        } else {
+            println!("{}\t{}\t{:?}\t{}", config.task_id(), name, due.state, next);
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn cmd_validate(dir: &str) -> ExitCode {
+    let diagnostics = validate_directory(dir);
+    for diag in &diagnostics {
+        let icon = if diag.is_error() { "❌" } else { "⚠️" };
+        println!("{} {}", icon, diag);
+    }
+    if diagnostics.iter().any(|d| d.is_error()) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,208 @@
+use crate::http::RequestError;
+use reqwest::blocking::multipart::{Form, Part};
+use reqwest::blocking::RequestBuilder;
+use reqwest::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+/// 请求体的编码方式 (任务配置中的 `body_type` 字段)
+///
+/// - json: body 按 JSON 发送 (默认)
+/// - form: body 为对象, 按 application/x-www-form-urlencoded 发送
+/// - text: body 为字符串, 按 text/plain 发送
+/// - raw: body 为 `{"file": "...", "content_type": "..."}`, 发送文件原始内容
+/// - multipart: body 为对象, 值为 `{"file": ...}` 的字段作为文件上传, 其余为普通字段
+///
+/// 读取配置时不区分大小写, 与校验一致
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", try_from = "String")]
+pub enum BodyType {
+    #[default]
+    Json,
+    Form,
+    Text,
+    Raw,
+    Multipart,
+}
+
+impl TryFrom<String> for BodyType {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, String> {
+        BodyType::parse(&s)
+    }
+}
+
+impl BodyType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BodyType::Json => "json",
+            BodyType::Form => "form",
+            BodyType::Text => "text",
+            BodyType::Raw => "raw",
+            BodyType::Multipart => "multipart",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(BodyType::Json),
+            "form" => Ok(BodyType::Form),
+            "text" => Ok(BodyType::Text),
+            "raw" => Ok(BodyType::Raw),
+            "multipart" => Ok(BodyType::Multipart),
+            _ => Err(format!(
+                "未知的 body_type: {} (可选: json/form/text/raw/multipart)",
+                s
+            )),
+        }
+    }
+
+    /// 检查 body 的结构是否符合该编码方式, 供配置校验使用
+    pub fn check(&self, body: &Value) -> Result<(), String> {
+        match self {
+            BodyType::Json => Ok(()),
+            BodyType::Form => match body {
+                Value::Object(fields) if !fields.values().any(is_file) => Ok(()),
+                Value::Object(_) => Err("form 不支持文件字段, 请改用 multipart".to_string()),
+                _ => Err("body_type 为 form 时 body 必须是 JSON 对象".to_string()),
+            },
+            BodyType::Text => match body {
+                Value::String(_) => Ok(()),
+                _ => Err("body_type 为 text 时 body 必须是字符串".to_string()),
+            },
+            BodyType::Raw if is_file(body) => FilePart::parse(body).map(|_| ()),
+            BodyType::Raw => {
+                Err("body_type 为 raw 时 body 必须是 {\"file\": \"路径\"}".to_string())
+            }
+            BodyType::Multipart => {
+                match body {
+                    Value::Object(fields) => fields
+                        .iter()
+                        .filter(|(_, v)| is_file(v))
+                        .try_for_each(|(name, v)| {
+                            FilePart::parse(v)
+                                .map(|_| ())
+                                .map_err(|e| format!("{}: {}", name, e))
+                        }),
+                    _ => Err("body_type 为 multipart 时 body 必须是 JSON 对象".to_string()),
+                }
+            }
+        }
+    }
+
+    /// 按编码方式设置请求体
+    ///
+    /// `has_content_type` 为 true 时 (headers 中已指定) 不再设置默认的 Content-Type。
+    pub fn apply(
+        &self,
+        builder: RequestBuilder,
+        body: &Value,
+        has_content_type: bool,
+    ) -> Result<RequestBuilder, RequestError> {
+        self.check(body).map_err(RequestError::Config)?;
+        let with_type = |builder: RequestBuilder, content_type: &str| {
+            if has_content_type {
+                builder
+            } else {
+                builder.header(CONTENT_TYPE, content_type)
+            }
+        };
+        Ok(match (self, body) {
+            (BodyType::Form, Value::Object(fields)) => builder.form(&form_fields(fields)),
+            (BodyType::Text, Value::String(text)) => {
+                with_type(builder, "text/plain; charset=utf-8").body(text.clone())
+            }
+            (BodyType::Raw, _) => {
+                let part = FilePart::parse(body).map_err(RequestError::Config)?;
+                let content = part.read()?;
+                let content_type = part
+                    .content_type
+                    .as_deref()
+                    .unwrap_or("application/octet-stream");
+                with_type(builder, content_type).body(content)
+            }
+            (BodyType::Multipart, Value::Object(fields)) => {
+                builder.multipart(multipart_form(fields)?)
+            }
+            _ => builder.json(body),
+        })
+    }
+}
+
+/// 从文件读取的请求体或 multipart 文件字段: `{"file": "...", "filename": "...", "content_type": "..."}`
+///
+/// 相对路径相对于运行目录 (仓库根目录)。
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FilePart {
+    pub file: String,
+    /// multipart 中的文件名, 默认取路径的最后一段
+    #[serde(default)]
+    pub filename: Option<String>,
+    #[serde(default)]
+    pub content_type: Option<String>,
+}
+
+impl FilePart {
+    pub fn parse(value: &Value) -> Result<Self, String> {
+        serde_json::from_value(value.clone()).map_err(|e| format!("文件字段格式错误: {}", e))
+    }
+
+    fn read(&self) -> Result<Vec<u8>, RequestError> {
+        fs::read(&self.file)
+            .map_err(|e| RequestError::Config(format!("读取文件失败 {}: {}", self.file, e)))
+    }
+
+    fn to_part(&self) -> Result<Part, RequestError> {
+        let filename = self.filename.clone().unwrap_or_else(|| {
+            Path::new(&self.file)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| self.file.clone())
+        });
+        let part = Part::bytes(self.read()?).file_name(filename);
+        match &self.content_type {
+            Some(ct) => part
+                .mime_str(ct)
+                .map_err(|e| RequestError::Config(format!("content_type 格式错误 {}: {}", ct, e))),
+            None => Ok(part),
+        }
+    }
+}
+
+/// 含 `file` 键的对象视为文件
+pub fn is_file(value: &Value) -> bool {
+    matches!(value, Value::Object(m) if m.contains_key("file"))
+}
+
+// 表单字段只能是字符串: 数字/布尔原样转换, 数组和对象转为 JSON 文本, null 为空
+fn field_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn form_fields(fields: &Map<String, Value>) -> Vec<(String, String)> {
+    fields
+        .iter()
+        .map(|(k, v)| (k.clone(), field_text(v)))
+        .collect()
+}
+
+fn multipart_form(fields: &Map<String, Value>) -> Result<Form, RequestError> {
+    let mut form = Form::new();
+    for (name, value) in fields {
+        form = if is_file(value) {
+            let file = FilePart::parse(value).map_err(RequestError::Config)?;
+            form.part(name.clone(), file.to_part()?)
+        } else {
+            form.text(name.clone(), field_text(value))
+        };
+    }
+    Ok(form)
+}
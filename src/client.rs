@@ -0,0 +1,144 @@
+// pyo3 0.20 为 #[new] 生成的代码会触发新版 rustc 的 non_local_definitions
+#![allow(non_local_definitions)]
+
+use crate::auth::resolve_headers;
+use crate::http::{self, RequestError, RequestSpec};
+use crate::limit::HostLimiter;
+use crate::response::Response;
+use pyo3::prelude::*;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// 构建 HttpClient 的参数
+#[derive(Debug, Clone, Default)]
+pub struct ClientOptions {
+    /// 每个请求都带上的请求头, 值中的 `${NAME}` 替换为环境变量
+    pub headers: BTreeMap<String, String>,
+    /// 代理地址, 如 `http://127.0.0.1:7890`; 为 None 时沿用 HTTP(S)_PROXY 环境变量
+    pub proxy: Option<String>,
+    pub connect_timeout: Option<Duration>,
+    pub user_agent: Option<String>,
+    /// 每个主机保留的空闲连接数上限
+    pub pool_max_idle_per_host: Option<usize>,
+    /// 每个主机每秒最多发起的请求数 (含重试), 为 None 时不限制
+    pub host_rate_limit: Option<f64>,
+}
+
+/// 可复用的 HTTP Client
+///
+/// 内部的连接池在各任务与重试之间共享, 发往同一主机 (如 api.day.app) 的请求不必每次重新握手。
+/// clone 只复制引用, 各副本共用同一个连接池。
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    inner: reqwest::blocking::Client,
+    options: ClientOptions,
+    limiter: Option<Arc<HostLimiter>>,
+}
+
+impl HttpClient {
+    pub fn new(options: ClientOptions) -> Result<Self, RequestError> {
+        let mut default_headers = HeaderMap::new();
+        for (name, value) in resolve_headers(&options.headers).map_err(RequestError::Config)? {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| RequestError::Config(format!("请求头名称无效 {}: {}", name, e)))?;
+            let value = HeaderValue::from_str(&value)
+                .map_err(|e| RequestError::Config(format!("请求头 {} 的值无效: {}", name, e)))?;
+            default_headers.append(name, value);
+        }
+        // 重定向由 http::send 逐跳跟随
+        let mut builder = reqwest::blocking::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .default_headers(default_headers);
+        if let Some(proxy) = &options.proxy {
+            let proxy = reqwest::Proxy::all(proxy.as_str())
+                .map_err(|e| RequestError::Config(format!("代理地址无效 {}: {}", proxy, e)))?;
+            builder = builder.proxy(proxy);
+        }
+        if let Some(timeout) = options.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(user_agent) = &options.user_agent {
+            builder = builder.user_agent(user_agent.as_str());
+        }
+        if let Some(max) = options.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        let limiter = match options.host_rate_limit {
+            Some(rate) => Some(Arc::new(HostLimiter::new(rate).map_err(|e| {
+                RequestError::Config(format!("host_rate_limit 无效: {}", e))
+            })?)),
+            None => None,
+        };
+        let inner = builder.build().map_err(RequestError::Client)?;
+        Ok(HttpClient {
+            inner,
+            options,
+            limiter,
+        })
+    }
+
+    /// 进程内共享的默认 Client, 未指定 client 时使用
+    ///
+    /// 与 reqwest::blocking::Client::new 一样, TLS 后端无法初始化时 panic。
+    pub fn shared() -> HttpClient {
+        static SHARED: OnceLock<HttpClient> = OnceLock::new();
+        SHARED
+            .get_or_init(|| {
+                HttpClient::new(ClientOptions::default()).expect("初始化 HTTP Client 失败")
+            })
+            .clone()
+    }
+
+    pub fn send(&self, spec: &RequestSpec) -> Result<Response, RequestError> {
+        if let Some(limiter) = &self.limiter {
+            limiter.wait(&spec.url);
+        }
+        http::send(&self.inner, spec)
+    }
+}
+
+#[pymethods]
+impl HttpClient {
+    // headers 为每个请求都带上的请求头 (支持 ${NAME}), proxy 为代理地址
+    // host_rate_limit 为每个主机每秒最多发起的请求数, 共用该 Client 的请求 (含重试) 一起计算
+    // 构建后传给 send_request / send_with_retry 的 client 参数, 在多次请求之间复用连接
+    #[new]
+    #[pyo3(signature = (headers=None, proxy=None, connect_timeout_secs=None, user_agent=None, pool_max_idle_per_host=None, host_rate_limit=None))]
+    fn py_new(
+        headers: Option<BTreeMap<String, String>>,
+        proxy: Option<String>,
+        connect_timeout_secs: Option<f64>,
+        user_agent: Option<String>,
+        pool_max_idle_per_host: Option<usize>,
+        host_rate_limit: Option<f64>,
+    ) -> PyResult<Self> {
+        let connect_timeout = match connect_timeout_secs {
+            Some(secs) => Some(Duration::try_from_secs_f64(secs).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "connect_timeout_secs 无效: {}",
+                    e
+                ))
+            })?),
+            None => None,
+        };
+        Ok(HttpClient::new(ClientOptions {
+            headers: headers.unwrap_or_default(),
+            proxy,
+            connect_timeout,
+            user_agent,
+            pool_max_idle_per_host,
+            host_rate_limit,
+        })?)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "HttpClient(headers={:?}, proxy={:?})",
+            self.options.headers.keys().collect::<Vec<_>>(),
+            self.options.proxy
+        )
+    }
+}
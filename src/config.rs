@@ -0,0 +1,480 @@
+use crate::atomic::write_atomic;
+use crate::auth::Auth;
+use crate::body::BodyType;
+use crate::due::DueResult;
+use crate::errors;
+use crate::expect::Expect;
+use crate::misfire::MisfirePolicy;
+use crate::preserve::render_update;
+use crate::retry::RetryPolicy;
+use crate::schedule::Schedule;
+use crate::tz::DstPolicy;
+use chrono::{Duration, NaiveDateTime};
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// 配置文件中 `trigger_time` / `executed_at` 使用的时间格式
+pub const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+fn default_timezone() -> String {
+    "Asia/Shanghai".to_string()
+}
+
+fn default_method() -> String {
+    "POST".to_string()
+}
+
+// 由程序维护的执行状态字段, 不参与 task_id 计算
+const STATE_FIELDS: &[&str] = &["id", "executed", "executed_at", "executed_occurrences"];
+
+// 递归按键排序, 使哈希与键顺序无关
+fn canonicalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<_> = map.keys().collect();
+            keys.sort();
+            let sorted = keys
+                .into_iter()
+                .map(|k| (k.clone(), canonicalize(&map[k])))
+                .collect();
+            Value::Object(sorted)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonicalize).collect()),
+        other => other.clone(),
+    }
+}
+
+fn is_default_policy(p: &DstPolicy) -> bool {
+    *p == DstPolicy::default()
+}
+
+fn default_body() -> Value {
+    Value::Object(Map::new())
+}
+
+fn is_default_body_type(t: &BodyType) -> bool {
+    *t == BodyType::default()
+}
+
+fn is_default_misfire(p: &MisfirePolicy) -> bool {
+    *p == MisfirePolicy::default()
+}
+
+// trigger_time 在 JSON 中是字符串, 在 Rust 中是 NaiveDateTime
+pub(crate) mod time_format {
+    use super::TIME_FORMAT;
+    use chrono::NaiveDateTime;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(t: &NaiveDateTime, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&t.format(TIME_FORMAT).to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<NaiveDateTime, D::Error> {
+        let raw = String::deserialize(d)?;
+        NaiveDateTime::parse_from_str(&raw, TIME_FORMAT).map_err(|e| {
+            de::Error::custom(format!(
+                "时间 '{}' 不符合格式 YYYY-MM-DD HH:MM:SS: {}",
+                raw, e
+            ))
+        })
+    }
+
+    // 可选字段版本, 配合 #[serde(default)] 使用
+    pub mod option {
+        use chrono::NaiveDateTime;
+        use serde::{Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(
+            t: &Option<NaiveDateTime>,
+            s: S,
+        ) -> Result<S::Ok, S::Error> {
+            match t {
+                Some(t) => super::serialize(t, s),
+                None => s.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            d: D,
+        ) -> Result<Option<NaiveDateTime>, D::Error> {
+            super::deserialize(d).map(Some)
+        }
+    }
+}
+
+/// 解析 "45s" / "30m" / "2h" / "1d" 形式的时长
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let err = || format!("时长格式错误 '{}': 应为数字加单位 s/m/h/d, 如 30m、2h", s);
+    let s = s.trim();
+    let unit = s.chars().last().ok_or_else(err)?;
+    let n: i64 = s[..s.len() - unit.len_utf8()].parse().map_err(|_| err())?;
+    if n < 0 {
+        return Err(err());
+    }
+    let duration = match unit {
+        's' => Duration::try_seconds(n),
+        'm' => Duration::try_minutes(n),
+        'h' => Duration::try_hours(n),
+        'd' => Duration::try_days(n),
+        _ => return Err(err()),
+    };
+    duration.ok_or_else(|| format!("时长超出范围 '{}'", s))
+}
+
+// 时长在 JSON 中是 "2h" 这样的字符串
+pub(crate) mod duration_format {
+    use chrono::Duration;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    // 取能整除的最大单位
+    pub fn format(d: Duration) -> String {
+        let secs = d.num_seconds();
+        match secs {
+            s if s != 0 && s % 86_400 == 0 => format!("{}d", s / 86_400),
+            s if s != 0 && s % 3_600 == 0 => format!("{}h", s / 3_600),
+            s if s != 0 && s % 60 == 0 => format!("{}m", s / 60),
+            s => format!("{}s", s),
+        }
+    }
+
+    pub fn serialize<S: Serializer>(d: &Duration, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&format(*d))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+        let raw = String::deserialize(d)?;
+        super::parse_duration(&raw).map_err(de::Error::custom)
+    }
+}
+
+/// 一个任务配置文件 (configs/*.json) 的类型化表示
+///
+/// 单次任务使用 `trigger_time`, 周期任务使用 `schedule`, 二者至少其一。
+/// 未识别的字段保存在 `extra` 中, 保存时原样写回。
+#[pyclass]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskConfig {
+    /// 显式指定的任务 id, 省略时使用任务定义的内容哈希
+    #[pyo3(get, set)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[pyo3(get, set)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_name: Option<String>,
+    #[serde(
+        default,
+        with = "time_format::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub trigger_time: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<Schedule>,
+    #[pyo3(get, set)]
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    /// 夏令时切换时不存在/重复的本地时间如何处理
+    #[serde(default, skip_serializing_if = "is_default_policy")]
+    pub dst_policy: DstPolicy,
+    /// 错过触发时间后是否补发
+    #[serde(default, skip_serializing_if = "is_default_misfire")]
+    pub misfire_policy: MisfirePolicy,
+    #[pyo3(get, set)]
+    pub webhook_url: String,
+    #[pyo3(get, set)]
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// 查询参数; 未设置时 GET 请求把 body 作为查询参数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<Map<String, Value>>,
+    /// 请求体, 结构取决于 body_type (json 时通常为对象)
+    #[serde(default = "default_body")]
+    pub body: Value,
+    /// 请求体的编码方式
+    #[serde(default, skip_serializing_if = "is_default_body_type")]
+    pub body_type: BodyType,
+    /// 额外的请求头, 值中的 `${NAME}` 在发送前替换为环境变量
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// 请求认证方式
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+    /// 判定成功的断言, 省略时 2xx 即成功
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect: Option<Expect>,
+    /// 发送失败时的重试策略, 省略时使用运行器的默认策略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    #[pyo3(get, set)]
+    #[serde(default)]
+    pub executed: bool,
+    #[pyo3(get, set)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub executed_at: Option<String>,
+    /// 周期任务已执行过的触发点 (本地时间, TIME_FORMAT)
+    #[pyo3(get, set)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub executed_occurrences: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl TaskConfig {
+    /// 从 JSON 文本解析, 出错时错误信息带有字段路径 (如 `body.title`)
+    pub fn parse(content: &str) -> Result<Self, serde_path_to_error::Error<serde_json::Error>> {
+        let de = &mut serde_json::Deserializer::from_str(content);
+        serde_path_to_error::deserialize(de)
+    }
+
+    /// 生成写回文件的文本
+    ///
+    /// `existing` 为当前文件内容: 只有与原文件解析结果不同的字段会被改写,
+    /// 其余文本 (包括键顺序、缩进、紧凑写法和末尾换行) 逐字保留, 使 git diff 只包含状态变化。
+    pub fn to_json(&self, existing: Option<&str>) -> serde_json::Result<String> {
+        let after = serde_json::to_value(self)?;
+        let before = existing
+            .and_then(|c| Self::parse(c).ok())
+            .and_then(|orig| serde_json::to_value(orig).ok());
+        render_update(existing, before, &after)
+    }
+
+    /// 执行状态使用的稳定 id
+    ///
+    /// 优先使用配置中的 `id`; 否则为任务定义 (不含执行状态字段) 的 SHA-256 前 16 位,
+    /// 与键顺序、排版无关, 只有修改了任务内容才会变化。
+    pub fn task_id(&self) -> String {
+        if let Some(id) = &self.id {
+            return id.clone();
+        }
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Value::Object(map) = &mut value {
+            for key in STATE_FIELDS {
+                map.shift_remove(*key);
+            }
+        }
+        let canonical = canonicalize(&value).to_string();
+        let digest = Sha256::digest(canonical.as_bytes());
+        let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        format!("sha256:{}", &hex[..16])
+    }
+
+    /// 根据 evaluate 的结果记录一次成功执行
+    ///
+    /// 单次任务置 `executed = true`; 周期任务只记录本次触发点。
+    pub fn record_execution(&mut self, due: &DueResult) {
+        if self.schedule.is_some() {
+            if let Some(t) = due.trigger_time {
+                let key = t.naive_local().format(TIME_FORMAT).to_string();
+                if !self.executed_occurrences.contains(&key) {
+                    self.executed_occurrences.push(key);
+                }
+            }
+        } else {
+            self.executed = true;
+        }
+        self.executed_at = Some(due.now_local());
+    }
+}
+
+#[pymethods]
+impl TaskConfig {
+    /// 读取并解析配置文件, 文件不存在时抛出 ConfigNotFound, 格式错误时抛出 ConfigParseError
+    #[staticmethod]
+    fn load(path: String, py: Python) -> PyResult<Self> {
+        let content = py
+            .allow_threads(|| fs::read_to_string(&path))
+            .map_err(|e| errors::read_error(py, &path, format!("读取失败 {}: {}", path, e), &e))?;
+        Self::parse(&content).map_err(|e| {
+            let message = format!("配置格式错误 {} (字段 {}): {}", path, e.path(), e.inner());
+            errors::parse_error(py, &path, message, e.inner(), Some(e.path().to_string()))
+        })
+    }
+
+    /// 原子地写回配置文件, backup=True 时保留旧文件为 .bak
+    #[pyo3(signature = (path, backup=false))]
+    fn save(&self, path: String, backup: bool, py: Python) -> PyResult<()> {
+        let existing = py.allow_threads(|| fs::read_to_string(&path).ok());
+        let content = self.to_json(existing.as_deref()).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("JSON 序列化失败: {}", e))
+        })?;
+        py.allow_threads(|| write_atomic(Path::new(&path), content.as_bytes(), backup))
+            .map_err(|e| errors::write_error(py, &path, format!("写入失败 {}: {}", path, e), &e))
+    }
+
+    /// 按 evaluate_due 的结果标记执行成功, 之后需调用 save 写回
+    fn mark_executed(&mut self, due: PyRef<DueResult>) {
+        self.record_execution(&due);
+    }
+
+    #[getter]
+    fn get_trigger_time(&self) -> Option<String> {
+        self.trigger_time.map(|t| t.format(TIME_FORMAT).to_string())
+    }
+
+    #[setter]
+    fn set_trigger_time(&mut self, value: Option<String>) -> PyResult<()> {
+        self.trigger_time = match value {
+            Some(v) => Some(NaiveDateTime::parse_from_str(&v, TIME_FORMAT).map_err(|e| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "时间格式错误 {}: {}",
+                    v, e
+                ))
+            })?),
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// 执行状态使用的稳定 id
+    #[getter(task_id)]
+    fn py_task_id(&self) -> String {
+        self.task_id()
+    }
+
+    /// "earliest" / "latest" / "reject"
+    #[getter]
+    fn get_dst_policy(&self) -> &'static str {
+        self.dst_policy.as_str()
+    }
+
+    #[setter]
+    fn set_dst_policy(&mut self, value: &str) -> PyResult<()> {
+        self.dst_policy =
+            DstPolicy::parse(value).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+        Ok(())
+    }
+
+    /// "skip" / "fire_once" / "fire_all" / "fire_if_within: 6h"
+    #[getter]
+    fn get_misfire_policy(&self) -> String {
+        self.misfire_policy.to_string()
+    }
+
+    /// 周期计划 (字典), 单次任务为 None
+    #[getter]
+    fn get_schedule(&self, py: Python) -> PyResult<PyObject> {
+        pythonize::pythonize(py, &self.schedule)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+    }
+
+    /// 成功断言 (字典), 未配置时为 None
+    #[getter]
+    fn get_expect(&self, py: Python) -> PyResult<PyObject> {
+        pythonize::pythonize(py, &self.expect)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+    }
+
+    /// 重试策略 (字典), 未配置时为 None
+    #[getter]
+    fn get_retry(&self, py: Python) -> PyResult<PyObject> {
+        pythonize::pythonize(py, &self.retry)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+    }
+
+    /// 查询参数 (字典), 未配置时为 None
+    #[getter]
+    fn get_query(&self, py: Python) -> PyResult<PyObject> {
+        pythonize::pythonize(py, &self.query)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+    }
+
+    /// 请求头 (未替换环境变量)
+    #[getter]
+    fn get_headers(&self) -> BTreeMap<String, String> {
+        self.headers.clone()
+    }
+
+    /// 认证方式 (字典, 未替换环境变量), 未配置时为 None
+    #[getter]
+    fn get_auth(&self, py: Python) -> PyResult<PyObject> {
+        pythonize::pythonize(py, &self.auth)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+    }
+
+    #[getter]
+    fn get_body(&self, py: Python) -> PyResult<PyObject> {
+        pythonize::pythonize(py, &self.body)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+    }
+
+    #[setter]
+    fn set_body(&mut self, value: &PyAny) -> PyResult<()> {
+        self.body = pythonize::depythonize(value).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("body 格式错误: {}", e))
+        })?;
+        Ok(())
+    }
+
+    /// "json" / "form" / "text" / "raw" / "multipart"
+    #[getter]
+    fn get_body_type(&self) -> &'static str {
+        self.body_type.as_str()
+    }
+
+    #[setter]
+    fn set_body_type(&mut self, value: &str) -> PyResult<()> {
+        self.body_type =
+            BodyType::parse(value).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+        Ok(())
+    }
+
+    /// 配置中未被识别的字段
+    #[getter]
+    fn get_extra(&self, py: Python) -> PyResult<PyObject> {
+        pythonize::pythonize(py, &self.extra)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+    }
+
+    /// 转为与 read_config 相同结构的字典
+    fn to_dict(&self, py: Python) -> PyResult<PyObject> {
+        pythonize::pythonize(py, self)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+    }
+
+    fn __repr__(&self) -> String {
+        let when = match (&self.schedule, self.trigger_time) {
+            (Some(s), _) => format!("schedule='{}'", s.cron),
+            (None, Some(t)) => format!("trigger_time='{}'", t.format(TIME_FORMAT)),
+            (None, None) => "trigger_time=None".to_string(),
+        };
+        format!(
+            "TaskConfig(task_name='{}', {}, executed={})",
+            self.task_name.as_deref().unwrap_or_default(),
+            when,
+            if self.executed { "True" } else { "False" }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_durations() {
+        assert_eq!(parse_duration("45s"), Ok(Duration::seconds(45)));
+        assert_eq!(parse_duration(" 30m "), Ok(Duration::minutes(30)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::hours(2)));
+        assert_eq!(parse_duration("1d"), Ok(Duration::days(1)));
+        for bad in ["", "m", "30", "-1h", "1.5h", "2w"] {
+            assert!(parse_duration(bad).is_err(), "{}", bad);
+        }
+        // 超出范围时报错而不是 panic
+        assert!(parse_duration("999999999999999d")
+            .unwrap_err()
+            .contains("超出范围"));
+    }
+
+    #[test]
+    fn format_durations() {
+        assert_eq!(duration_format::format(Duration::seconds(90)), "90s");
+        assert_eq!(duration_format::format(Duration::minutes(90)), "90m");
+        assert_eq!(duration_format::format(Duration::hours(48)), "2d");
+        assert_eq!(duration_format::format(Duration::zero()), "0s");
+    }
+}
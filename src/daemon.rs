@@ -0,0 +1,458 @@
+use crate::config::TaskConfig;
+use crate::due::{evaluate, DueState};
+use crate::runner::{load_config, load_store, process_task, RunOptions, TaskReport};
+use crate::state::StateStore;
+use chrono::{DateTime, Duration, Utc};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+
+/// 单次休眠的上限: 到点前也会定期醒来重新计算, 以应对系统休眠或时钟调整
+const MAX_SLEEP: Duration = Duration::seconds(60);
+
+/// 发送最终失败后, 间隔多久再次尝试 (仍需处于容忍窗口内)
+const RETRY_AFTER: Duration = Duration::seconds(60);
+
+/// 收到文件事件后再等待的时间, 合并编辑器保存时产生的连续事件
+const DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// 已加载的任务及其下一次唤醒时间
+#[derive(Debug, Clone)]
+struct Scheduled {
+    config: TaskConfig,
+    /// None 表示不会再触发 (已执行、已过期或配置无效)
+    next: Option<DateTime<Utc>>,
+}
+
+/// 配置文件变更后的处理结果
+#[derive(Debug, Clone)]
+pub enum Change {
+    Added,
+    Updated,
+    Removed,
+    /// 解析失败, 继续使用之前的有效版本 (如果有)
+    Invalid(String),
+}
+
+/// 常驻进程中发生的事件
+#[derive(Debug, Clone)]
+pub enum DaemonEvent {
+    Fired(Box<TaskReport>),
+    Reloaded {
+        file: String,
+        change: Change,
+    },
+    /// 不影响调度的错误, 如写入状态失败
+    Warning(String),
+}
+
+/// 常驻进程模式: 按每个任务的下一次触发时间精确休眠, 到点立即触发
+///
+/// 与 run_once 的轮询不同, 触发误差在秒级, 不再依赖 Actions 的调度间隔。
+pub struct Daemon {
+    opts: RunOptions,
+    store: Mutex<StateStore>,
+    /// 以配置文件路径为键, 见 task_key
+    tasks: BTreeMap<String, Scheduled>,
+    /// watch 启动后才有; 监听器必须与接收端一起保留, 否则会被释放
+    watcher: Option<(RecommendedWatcher, Receiver<PathBuf>)>,
+    /// 尚未交给调用方的警告, 见 take_warnings
+    warnings: Vec<String>,
+}
+
+impl Daemon {
+    /// 读取执行状态和全部配置; 读取失败的配置放在返回值中, 不会被调度
+    pub fn new(opts: RunOptions, now: DateTime<Utc>) -> Result<(Self, Vec<TaskReport>), String> {
+        let store = Mutex::new(load_store(&opts.state_path)?);
+        let mut daemon = Daemon {
+            opts,
+            store,
+            tasks: BTreeMap::new(),
+            watcher: None,
+            warnings: Vec::new(),
+        };
+        let mut errors = Vec::new();
+        for file in crate::scan_configs(&daemon.opts.config_dir) {
+            match load_config(&file) {
+                Ok(config) => daemon.schedule(&file, config, now),
+                Err(e) => errors.push(TaskReport {
+                    file,
+                    config: None,
+                    due: None,
+                    outcome: None,
+                    error: Some(e),
+                }),
+            }
+        }
+        Ok((daemon, errors))
+    }
+
+    /// 加入 (或替换) 一个任务并计算下一次唤醒时间
+    pub fn schedule(&mut self, file: &str, config: TaskConfig, now: DateTime<Utc>) {
+        let next = self.next_wakeup(&config, now);
+        self.tasks
+            .insert(task_key(Path::new(file)), Scheduled { config, next });
+    }
+
+    // 顺带记录 misfire_policy 放弃的触发点, 它们不会再有唤醒的机会
+    fn next_wakeup(&mut self, config: &TaskConfig, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let store = self.store.get_mut().unwrap();
+        let due = evaluate(config, store, now, self.opts.tolerance);
+        if store.record_skipped(config, &due) {
+            if let Err(e) = store.save(&self.opts.state_path) {
+                let path = self.opts.state_path.display();
+                self.warnings.push(format!("写入状态失败 {}: {}", path, e));
+            }
+        }
+        match due.state {
+            DueState::Due => Some(now),
+            DueState::Pending => due.trigger_time.map(|t| t.with_timezone(&Utc)),
+            _ => None,
+        }
+    }
+
+    /// 取出调度过程中积累的警告
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// 最早的唤醒时间, 没有待触发任务时为 None
+    pub fn next_deadline(&self) -> Option<DateTime<Utc>> {
+        self.tasks.values().filter_map(|t| t.next).min()
+    }
+
+    /// 已调度的任务数及其中仍会触发的任务数
+    pub fn counts(&self) -> (usize, usize) {
+        let pending = self.tasks.values().filter(|t| t.next.is_some()).count();
+        (self.tasks.len(), pending)
+    }
+
+    /// 触发所有唤醒时间已到的任务, 并重新计算它们的下一次唤醒时间
+    pub fn tick(&mut self, now: DateTime<Utc>) -> Vec<TaskReport> {
+        let ready: Vec<String> = self
+            .tasks
+            .iter()
+            .filter(|(_, t)| t.next.is_some_and(|next| next <= now))
+            .map(|(file, _)| file.clone())
+            .collect();
+        let mut reports = Vec::new();
+        for file in ready {
+            let config = self.tasks[&file].config.clone();
+            let report = process_task(&file, &config, &self.store, &self.opts, now);
+            let failed = report.outcome.as_ref().is_some_and(|o| !o.success);
+            let next = if failed {
+                Some(Utc::now() + RETRY_AFTER)
+            } else {
+                self.next_wakeup(&config, Utc::now())
+            };
+            if let Some(task) = self.tasks.get_mut(&file) {
+                task.next = next;
+            }
+            if report.outcome.is_some() {
+                reports.push(report);
+            }
+        }
+        reports
+    }
+
+    /// 重新读取一个配置文件并重新调度; 内容未变化时返回 None
+    ///
+    /// 解析失败时保留之前的有效版本, 避免保存到一半的文件让任务丢失。
+    pub fn reload(&mut self, file: &str, now: DateTime<Utc>) -> Option<Change> {
+        let key = task_key(Path::new(file));
+        if !Path::new(file).exists() {
+            return self.tasks.remove(&key).map(|_| Change::Removed);
+        }
+        let config = match load_config(file) {
+            Ok(c) => c,
+            Err(e) => return Some(Change::Invalid(e)),
+        };
+        let change = match self.tasks.get(&key) {
+            None => Change::Added,
+            Some(old) if same_config(&old.config, &config) => return None,
+            Some(_) => Change::Updated,
+        };
+        self.schedule(file, config, now);
+        Some(change)
+    }
+
+    /// 开始监听配置目录, 之后 run 会在文件变更时自动 reload
+    pub fn watch(&mut self) -> Result<(), String> {
+        let dir = self.opts.config_dir.clone();
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                for path in event.paths {
+                    let _ = tx.send(path);
+                }
+            }
+        })
+        .map_err(|e| format!("无法创建文件监听: {}", e))?;
+        watcher
+            .watch(Path::new(&dir), RecursiveMode::NonRecursive)
+            .map_err(|e| format!("无法监听目录 {}: {}", dir, e))?;
+        self.watcher = Some((watcher, rx));
+        Ok(())
+    }
+
+    /// 休眠至多 `wait`, 期间有配置文件变更时提前返回变更的文件 (形式同 task_key)
+    fn wait_for_changes(&self, wait: std::time::Duration) -> BTreeSet<String> {
+        let mut files = BTreeSet::new();
+        let Some((_, rx)) = &self.watcher else {
+            std::thread::sleep(wait);
+            return files;
+        };
+        let mut next = rx.recv_timeout(wait);
+        while let Ok(path) = next {
+            if let Some(file) = self.config_key(&path) {
+                files.insert(file);
+            }
+            next = rx.recv_timeout(DEBOUNCE);
+        }
+        if let Err(RecvTimeoutError::Disconnected) = next {
+            // 监听线程已退出, 退化为普通休眠
+            std::thread::sleep(wait);
+        }
+        files
+    }
+
+    // 只关心配置目录下的 .json 文件, 忽略原子写入的临时文件和 .bak
+    fn config_key(&self, path: &Path) -> Option<String> {
+        if path.extension()? != "json" {
+            return None;
+        }
+        let name = path.file_name()?.to_str()?;
+        if name.starts_with('.') {
+            return None;
+        }
+        Some(task_key(&Path::new(&self.opts.config_dir).join(name)))
+    }
+
+    /// 主循环, 不会返回; 每次触发或重新加载配置后调用 `on_event`
+    pub fn run(&mut self, mut on_event: impl FnMut(&DaemonEvent)) -> ! {
+        loop {
+            let now = Utc::now();
+            for report in self.tick(now) {
+                on_event(&DaemonEvent::Fired(Box::new(report)));
+            }
+            let now = Utc::now();
+            let wait = match self.next_deadline() {
+                Some(deadline) => (deadline - now).clamp(Duration::zero(), MAX_SLEEP),
+                None => MAX_SLEEP,
+            };
+            for warning in self.take_warnings() {
+                on_event(&DaemonEvent::Warning(warning));
+            }
+            for file in self.wait_for_changes(wait.to_std().unwrap_or_default()) {
+                if let Some(change) = self.reload(&file, Utc::now()) {
+                    on_event(&DaemonEvent::Reloaded { file, change });
+                }
+            }
+        }
+    }
+}
+
+// 同一文件的不同写法 (如 `configs//a.json` 与 `./configs/a.json`) 得到相同的键
+fn task_key(path: &Path) -> String {
+    let path: PathBuf = path
+        .components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect();
+    path.to_string_lossy().into_owned()
+}
+
+// 比较解析后的内容, 只改动空白或键顺序不算变化
+fn same_config(a: &TaskConfig, b: &TaskConfig) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry::RetryPolicy;
+    use crate::testing::{response, serve, serve_ok};
+    use std::fs;
+
+    // 临时目录下的 configs/ 与状态文件
+    fn setup(name: &str) -> (PathBuf, RunOptions) {
+        let root =
+            std::env::temp_dir().join(format!("task_io_daemon_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("configs")).unwrap();
+        let opts = RunOptions {
+            config_dir: root.join("configs").to_string_lossy().into_owned(),
+            state_path: root.join("state.json"),
+            history_path: None,
+            retry: RetryPolicy {
+                max_attempts: 1,
+                ..RetryPolicy::default()
+            },
+            ..RunOptions::default()
+        };
+        (root, opts)
+    }
+
+    fn write_task(root: &Path, name: &str, trigger_time: DateTime<Utc>, url: &str) {
+        let config = serde_json::json!({
+            "task_name": name,
+            "trigger_time": trigger_time.format(crate::TIME_FORMAT).to_string(),
+            "timezone": "UTC",
+            "webhook_url": url,
+        });
+        let path = root.join("configs").join(format!("{}.json", name));
+        fs::write(path, config.to_string()).unwrap();
+    }
+
+    #[test]
+    fn keys_do_not_depend_on_dir_spelling() {
+        let (root, opts) = setup("keys");
+        let later = Utc::now() + Duration::hours(1);
+        write_task(&root, "a", later, "http://127.0.0.1:9/hook");
+        let dir = opts.config_dir.clone();
+        for spelling in [
+            format!("{}/", dir),
+            format!("{}//", dir),
+            format!("{}/./", dir),
+        ] {
+            let opts = RunOptions {
+                config_dir: spelling.clone(),
+                ..opts.clone()
+            };
+            let (mut daemon, errors) = Daemon::new(opts, Utc::now()).unwrap();
+            assert!(errors.is_empty());
+            let file = daemon
+                .config_key(&root.join("configs").join("a.json"))
+                .unwrap();
+            assert_eq!(
+                daemon.tasks.keys().collect::<Vec<_>>(),
+                [&file],
+                "{}",
+                spelling
+            );
+            // 同一文件的事件不会被当成新任务
+            assert!(daemon.reload(&file, Utc::now()).is_none(), "{}", spelling);
+            assert_eq!(daemon.counts(), (1, 1));
+        }
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn reload_keeps_previous_version_on_bad_edit() {
+        let (root, opts) = setup("reload");
+        let later = Utc::now() + Duration::hours(1);
+        let (mut daemon, _) = Daemon::new(opts, Utc::now()).unwrap();
+        let path = root.join("configs").join("a.json");
+        let file = daemon.config_key(&path).unwrap();
+
+        write_task(&root, "a", later, "http://127.0.0.1:9/hook");
+        assert!(matches!(
+            daemon.reload(&file, Utc::now()),
+            Some(Change::Added)
+        ));
+        let next = daemon.next_deadline().unwrap();
+        assert_eq!(next.timestamp(), later.timestamp());
+
+        fs::write(&path, "{\"task_name\": ").unwrap();
+        assert!(matches!(
+            daemon.reload(&file, Utc::now()),
+            Some(Change::Invalid(_))
+        ));
+        assert_eq!(daemon.next_deadline(), Some(next));
+
+        write_task(
+            &root,
+            "a",
+            later + Duration::hours(1),
+            "http://127.0.0.1:9/hook",
+        );
+        assert!(matches!(
+            daemon.reload(&file, Utc::now()),
+            Some(Change::Updated)
+        ));
+        assert!(daemon.next_deadline().unwrap() > next);
+
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            daemon.reload(&file, Utc::now()),
+            Some(Change::Removed)
+        ));
+        assert_eq!(daemon.counts(), (0, 0));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn tick_fires_due_tasks_once() {
+        let (root, opts) = setup("tick");
+        let (base, server) = serve_ok();
+        let now = Utc::now();
+        write_task(&root, "due", now, &format!("{}/hook", base));
+        write_task(
+            &root,
+            "later",
+            now + Duration::hours(1),
+            &format!("{}/hook", base),
+        );
+        let (mut daemon, _) = Daemon::new(opts, now).unwrap();
+        assert_eq!(daemon.next_deadline(), Some(now));
+
+        let reports = daemon.tick(now);
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].file.ends_with("due.json"));
+        assert!(reports[0].outcome.as_ref().unwrap().success);
+        assert_eq!(server.join().unwrap().len(), 1);
+        // 已执行的任务不再唤醒, 状态已写入
+        assert_eq!(daemon.counts(), (2, 1));
+        assert!(daemon.tick(Utc::now()).is_empty());
+        assert!(root.join("state.json").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn failed_task_is_retried_later() {
+        let (root, opts) = setup("retry");
+        let (base, server) = serve(1, |_| response("500 Internal Server Error", &[], ""));
+        let now = Utc::now();
+        write_task(&root, "a", now, &format!("{}/hook", base));
+        let (mut daemon, _) = Daemon::new(opts, now).unwrap();
+
+        let before = Utc::now();
+        let reports = daemon.tick(now);
+        let after = Utc::now();
+        assert!(!reports[0].outcome.as_ref().unwrap().success);
+        assert_eq!(server.join().unwrap().len(), 1);
+        let next = daemon.next_deadline().unwrap();
+        assert!(next >= before + RETRY_AFTER && next <= after + RETRY_AFTER);
+        // 重试时间未到时不会再次发送
+        assert!(daemon.tick(after).is_empty());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn state_write_failure_becomes_warning() {
+        let (root, opts) = setup("warning");
+        let (mut daemon, _) = Daemon::new(opts, Utc::now()).unwrap();
+        // 状态文件路径换成一个目录, 记录放弃的触发点时写入失败
+        daemon.opts.state_path = root.clone();
+        write_task(
+            &root,
+            "a",
+            Utc::now() - Duration::hours(2),
+            "http://127.0.0.1:9/hook",
+        );
+        let file = daemon
+            .config_key(&root.join("configs").join("a.json"))
+            .unwrap();
+        assert!(matches!(
+            daemon.reload(&file, Utc::now()),
+            Some(Change::Added)
+        ));
+        assert_eq!(daemon.counts(), (1, 0));
+        let warnings = daemon.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("写入状态失败"), "{}", warnings[0]);
+        assert!(daemon.take_warnings().is_empty());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,638 @@
+use crate::config::{TaskConfig, TIME_FORMAT};
+use crate::errors;
+use crate::misfire::{MisfirePolicy, MAX_CATCHUP};
+use crate::schedule::Schedule;
+use crate::state::StateStore;
+use crate::tz::{localize, parse_timezone, DstPolicy};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use pyo3::prelude::*;
+use std::path::Path;
+
+/// 任务相对于当前时间所处的状态
+#[pyclass]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DueState {
+    /// 时间未到
+    Pending,
+    /// 处于 [trigger_time, trigger_time + tolerance] 窗口内, 应当执行
+    Due,
+    /// 已超过容忍窗口, 或周期计划已结束
+    Expired,
+    /// 配置中已标记 executed, 或周期计划最后一个触发点已执行
+    AlreadyExecuted,
+    /// 时区无效或本地时间不存在等, 无法判断
+    Invalid,
+}
+
+/// evaluate_due 的结果
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct DueResult {
+    #[pyo3(get)]
+    pub state: DueState,
+    pub trigger_time: Option<DateTime<Tz>>,
+    pub now: DateTime<Utc>,
+    pub tz: Option<Tz>,
+    #[pyo3(get)]
+    pub message: Option<String>,
+    /// 按 misfire_policy 放弃的触发点, 应记录到执行状态中
+    pub skipped: Vec<DateTime<Tz>>,
+}
+
+impl DueResult {
+    /// 当前时间减去触发时间, 为负表示时间未到
+    pub fn delay(&self) -> Option<Duration> {
+        self.trigger_time
+            .map(|t| self.now.signed_duration_since(t.with_timezone(&Utc)))
+    }
+
+    /// 下一次将会触发的时间, 只有 Pending / Due 时才有
+    pub fn next_fire(&self) -> Option<DateTime<Tz>> {
+        match self.state {
+            DueState::Pending | DueState::Due => self.trigger_time,
+            _ => None,
+        }
+    }
+
+    /// 任务时区下的当前时间, 时区无效时使用 UTC
+    pub fn now_local(&self) -> String {
+        match self.tz {
+            Some(tz) => self.now.with_timezone(&tz).format(TIME_FORMAT).to_string(),
+            None => self.now.format(TIME_FORMAT).to_string(),
+        }
+    }
+}
+
+#[pymethods]
+impl DueResult {
+    /// 带时区偏移的触发时间, 如 `2026-01-02 22:00:00+08:00`
+    #[getter]
+    fn get_trigger_time(&self) -> Option<String> {
+        self.trigger_time
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S%:z").to_string())
+    }
+
+    /// 任务时区下的当前时间 (TIME_FORMAT), 可直接写入 executed_at
+    #[getter]
+    fn get_now(&self) -> String {
+        self.now_local()
+    }
+
+    /// 放弃执行的触发点, 格式同 trigger_time
+    #[getter]
+    fn get_skipped(&self) -> Vec<String> {
+        self.skipped
+            .iter()
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S%:z").to_string())
+            .collect()
+    }
+
+    #[getter]
+    fn get_delay_minutes(&self) -> Option<f64> {
+        self.delay().map(|d| d.num_milliseconds() as f64 / 60_000.0)
+    }
+
+    fn __repr__(&self) -> String {
+        let trigger_time = match self.get_trigger_time() {
+            Some(t) => format!("'{}'", t),
+            None => "None".to_string(),
+        };
+        format!(
+            "DueResult(state={:?}, trigger_time={}, now='{}')",
+            self.state,
+            trigger_time,
+            self.now_local()
+        )
+    }
+}
+
+/// 判断任务在 `now` 时刻是否应当执行
+///
+/// 单次任务的规则与原先 Python 中一致: `0 <= now - trigger_time <= tolerance` 时为 Due。
+/// 周期任务对每个触发点套用同样的窗口, 并跳过已执行的触发点。
+/// 设置了 misfire_policy 时, 窗口与补发方式按策略调整, 放弃的触发点放在 `skipped` 中。
+/// 是否已执行由 `state` 判断, 它同时兼容配置文件中内联的 executed 字段。
+pub fn evaluate(
+    config: &TaskConfig,
+    state: &StateStore,
+    now: DateTime<Utc>,
+    tolerance: Duration,
+) -> DueResult {
+    let tz_name = config
+        .schedule
+        .as_ref()
+        .and_then(|s| s.timezone.as_deref())
+        .unwrap_or(&config.timezone);
+    let tz = parse_timezone(tz_name);
+    let mut result = DueResult {
+        state: DueState::Invalid,
+        trigger_time: None,
+        now,
+        tz: tz.as_ref().ok().copied(),
+        message: None,
+        skipped: Vec::new(),
+    };
+    if state.is_executed(config, None) {
+        result.state = DueState::AlreadyExecuted;
+        return result;
+    }
+    let tz = match tz {
+        Ok(tz) => tz,
+        Err(e) => {
+            result.message = Some(e);
+            return result;
+        }
+    };
+
+    match (&config.schedule, config.trigger_time) {
+        (Some(schedule), _) => evaluate_schedule(config, schedule, state, tz, tolerance, result),
+        (None, Some(trigger_time)) => evaluate_once(
+            trigger_time,
+            tz,
+            config.dst_policy,
+            config.misfire_policy.window(tolerance),
+            result,
+        ),
+        (None, None) => {
+            result.message = Some("缺少 trigger_time 或 schedule".to_string());
+            result
+        }
+    }
+}
+
+// window 为 misfire_policy 换算后的窗口, 超过窗口的触发时间记为放弃
+fn evaluate_once(
+    trigger_time: NaiveDateTime,
+    tz: Tz,
+    policy: DstPolicy,
+    window: Duration,
+    mut result: DueResult,
+) -> DueResult {
+    let trigger_time = match localize(tz, trigger_time, policy) {
+        Ok(t) => t,
+        Err(e) => {
+            result.message = Some(e.to_string());
+            return result;
+        }
+    };
+    result.trigger_time = Some(trigger_time);
+
+    let diff = result
+        .now
+        .signed_duration_since(trigger_time.with_timezone(&Utc));
+    result.state = if diff < Duration::zero() {
+        DueState::Pending
+    } else if diff <= window {
+        DueState::Due
+    } else {
+        result.skipped.push(trigger_time);
+        DueState::Expired
+    };
+    result
+}
+
+// 找出上次处理过的触发点之后所有错过的触发点, 再按 misfire_policy 决定补发哪一个:
+// fire_once 取最近一个并放弃其余, 其他策略取窗口内最早的一个, 窗口外的记为放弃
+fn evaluate_schedule(
+    config: &TaskConfig,
+    schedule: &Schedule,
+    state: &StateStore,
+    tz: Tz,
+    tolerance: Duration,
+    mut result: DueResult,
+) -> DueResult {
+    let cron = match schedule.parse_cron() {
+        Ok(c) => c,
+        Err(e) => {
+            result.message = Some(e);
+            return result;
+        }
+    };
+    let now = result.now;
+    let misfire = config.misfire_policy;
+    let window = misfire.window(tolerance);
+    let in_window = |at: &DateTime<Tz>| now.signed_duration_since(at.with_timezone(&Utc)) <= window;
+
+    // 有执行记录时从上次处理过的触发点开始找错过的触发点, 只补发 MAX_CATCHUP 以内的, 更早的记为放弃;
+    // 只有 start 时最多回溯 MAX_CATCHUP; 否则任务是新加入的, 只看窗口内, 补发策略也不补发加入之前的触发点
+    let anchor = state.last_handled(config);
+    let lower = if anchor.is_some() || schedule.start.is_some() {
+        now - MAX_CATCHUP
+    } else {
+        match misfire {
+            MisfirePolicy::FireOnce | MisfirePolicy::FireAll => now - tolerance,
+            _ => now - window.min(MAX_CATCHUP),
+        }
+    };
+    let mut scan_from = match anchor {
+        Some(anchor) => anchor,
+        None => lower.with_timezone(&tz).naive_local(),
+    };
+    // 多退一小时, 避免夏令时切换时漏掉开头的触发点
+    scan_from -= Duration::hours(1);
+
+    // task_id 需要计算哈希, 只算一次
+    let task_id = config.task_id();
+    let mut too_old = Vec::new();
+    let mut missed = Vec::new();
+    let mut next = None;
+    let mut last_executed = None;
+    for occurrence in schedule.local_occurrences(&cron, scan_from) {
+        // dst_policy 为 reject 时, 落在夏令时边界上的触发点作废
+        let Ok(at) = localize(tz, occurrence, config.dst_policy) else {
+            continue;
+        };
+        let at_utc = at.with_timezone(&Utc);
+        if at_utc > now {
+            next = Some(at);
+            break;
+        }
+        let executed = state.is_occurrence_executed(config, &task_id, &occurrence);
+        let handled = executed
+            || anchor.is_some_and(|a| occurrence <= a)
+            || state.is_skipped(&task_id, &occurrence);
+        if at_utc < lower {
+            if anchor.is_some() && !handled {
+                too_old.push(at);
+            }
+            continue;
+        }
+        if executed {
+            if in_window(&at) {
+                last_executed = Some(at);
+            }
+            continue;
+        }
+        if handled {
+            continue;
+        }
+        missed.push(at);
+    }
+
+    let due = match misfire {
+        MisfirePolicy::FireOnce => missed.iter().rposition(&in_window),
+        _ => missed.iter().position(&in_window),
+    };
+    result.skipped = too_old;
+    result.skipped.extend(
+        missed
+            .iter()
+            .enumerate()
+            .filter(|(i, at)| match due {
+                Some(d) if misfire == MisfirePolicy::FireOnce => *i != d,
+                _ => !in_window(at),
+            })
+            .map(|(_, at)| *at),
+    );
+
+    match (due, next, last_executed) {
+        (Some(d), _, _) => {
+            result.trigger_time = Some(missed[d]);
+            result.state = DueState::Due;
+        }
+        (None, Some(at), _) => {
+            result.trigger_time = Some(at);
+            result.state = DueState::Pending;
+        }
+        // 计划已结束 (超过 end 或用完 count)
+        (None, None, Some(at)) if result.skipped.is_empty() => {
+            result.trigger_time = Some(at);
+            result.state = DueState::AlreadyExecuted;
+        }
+        (None, None, _) => {
+            result.state = DueState::Expired;
+            result.message = Some("周期计划已结束".to_string());
+        }
+    }
+    result
+}
+
+// 判断任务是否到期; now 为带时区的 datetime, 省略时取当前时间
+// state_path 为执行状态文件, 省略时只看配置中内联的 executed 字段
+#[pyfunction]
+#[pyo3(signature = (config, now=None, tolerance_minutes=30, state_path=None))]
+pub fn evaluate_due(
+    config: PyRef<TaskConfig>,
+    now: Option<DateTime<FixedOffset>>,
+    tolerance_minutes: i64,
+    state_path: Option<String>,
+    py: Python,
+) -> PyResult<DueResult> {
+    let state = match state_path {
+        Some(path) => py
+            .allow_threads(|| StateStore::load(Path::new(&path)))
+            .map_err(|e| {
+                errors::read_error(py, &path, format!("读取状态失败 {}: {}", path, e), &e)
+            })?,
+        None => StateStore::default(),
+    };
+    let now = now.map(|t| t.with_timezone(&Utc)).unwrap_or_else(Utc::now);
+    Ok(evaluate(
+        &config,
+        &state,
+        now,
+        Duration::minutes(tolerance_minutes),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn task(fields: serde_json::Value) -> TaskConfig {
+        let mut value = json!({"webhook_url": "https://example.com/hook", "timezone": "UTC"});
+        value
+            .as_object_mut()
+            .unwrap()
+            .extend(fields.as_object().unwrap().clone());
+        TaskConfig::parse(&value.to_string()).unwrap()
+    }
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        NaiveDateTime::parse_from_str(s, TIME_FORMAT)
+            .unwrap()
+            .and_utc()
+    }
+
+    fn local(times: &[DateTime<Tz>]) -> Vec<String> {
+        times
+            .iter()
+            .map(|t| t.naive_local().format(TIME_FORMAT).to_string())
+            .collect()
+    }
+
+    // 默认 30 分钟容忍窗口下的 evaluate
+    fn eval(config: &TaskConfig, state: &StateStore, now: &str) -> DueResult {
+        evaluate(config, state, utc(now), Duration::minutes(30))
+    }
+
+    fn trigger(due: &DueResult) -> String {
+        local(&[due.trigger_time.unwrap()]).remove(0)
+    }
+
+    #[test]
+    fn once_window_boundaries() {
+        let config = task(json!({"trigger_time": "2026-01-02 22:00:00"}));
+        let state = StateStore::default();
+        let cases = [
+            ("2026-01-02 21:59:59", DueState::Pending),
+            ("2026-01-02 22:00:00", DueState::Due),
+            ("2026-01-02 22:30:00", DueState::Due),
+            ("2026-01-02 22:30:01", DueState::Expired),
+        ];
+        for (now, expected) in cases {
+            let due = eval(&config, &state, now);
+            assert_eq!(due.state, expected, "{}", now);
+            assert_eq!(trigger(&due), "2026-01-02 22:00:00");
+        }
+        let expired = eval(&config, &state, "2026-01-02 22:30:01");
+        assert_eq!(local(&expired.skipped), ["2026-01-02 22:00:00"]);
+    }
+
+    #[test]
+    fn once_uses_task_timezone() {
+        let config = task(json!({
+            "trigger_time": "2026-01-02 22:00:00",
+            "timezone": "Asia/Shanghai",
+        }));
+        let state = StateStore::default();
+        assert_eq!(
+            eval(&config, &state, "2026-01-02 13:59:59").state,
+            DueState::Pending
+        );
+        let due = eval(&config, &state, "2026-01-02 14:00:00");
+        assert_eq!(due.state, DueState::Due);
+        assert_eq!(due.delay(), Some(Duration::zero()));
+        assert_eq!(due.now_local(), "2026-01-02 22:00:00");
+    }
+
+    #[test]
+    fn once_already_executed() {
+        let inline = task(json!({"trigger_time": "2026-01-02 22:00:00", "executed": true}));
+        let due = eval(&inline, &StateStore::default(), "2026-01-02 22:00:00");
+        assert_eq!(due.state, DueState::AlreadyExecuted);
+
+        let config = task(json!({"trigger_time": "2026-01-02 22:00:00"}));
+        let mut state = StateStore::default();
+        let due = eval(&config, &state, "2026-01-02 22:05:00");
+        state.record(&config, &due);
+        let again = eval(&config, &state, "2026-01-02 22:10:00");
+        assert_eq!(again.state, DueState::AlreadyExecuted);
+    }
+
+    #[test]
+    fn invalid_timezone_and_missing_time() {
+        let config =
+            task(json!({"trigger_time": "2026-01-02 22:00:00", "timezone": "Mars/Olympus"}));
+        let due = eval(&config, &StateStore::default(), "2026-01-02 22:00:00");
+        assert_eq!(due.state, DueState::Invalid);
+        assert!(due.message.unwrap().contains("Mars/Olympus"));
+        assert_eq!(due.tz, None);
+
+        let config = task(json!({}));
+        let due = eval(&config, &StateStore::default(), "2026-01-02 22:00:00");
+        assert_eq!(due.state, DueState::Invalid);
+    }
+
+    #[test]
+    fn once_dst_policies() {
+        // 2026-03-08 02:30 在纽约不存在
+        let fields = |policy: &str| {
+            json!({
+                "trigger_time": "2026-03-08 02:30:00",
+                "timezone": "America/New_York",
+                "dst_policy": policy,
+            })
+        };
+        let state = StateStore::default();
+        let earliest = eval(&task(fields("earliest")), &state, "2026-03-08 06:30:00");
+        assert_eq!(earliest.state, DueState::Due);
+        assert_eq!(earliest.delay(), Some(Duration::zero()));
+        let latest = eval(&task(fields("latest")), &state, "2026-03-08 06:30:00");
+        assert_eq!(latest.state, DueState::Pending);
+        assert_eq!(latest.delay(), Some(Duration::hours(-1)));
+        let reject = eval(&task(fields("reject")), &state, "2026-03-08 06:30:00");
+        assert_eq!(reject.state, DueState::Invalid);
+        assert!(reject.message.unwrap().contains("不存在"));
+    }
+
+    #[test]
+    fn schedule_pending_and_due() {
+        // 2026-01-02 为周五
+        let config = task(json!({"schedule": {"cron": "0 22 * * 5"}}));
+        let state = StateStore::default();
+        let pending = eval(&config, &state, "2026-01-02 21:00:00");
+        assert_eq!(pending.state, DueState::Pending);
+        assert_eq!(trigger(&pending), "2026-01-02 22:00:00");
+        let due = eval(&config, &state, "2026-01-02 22:30:00");
+        assert_eq!(due.state, DueState::Due);
+        assert_eq!(trigger(&due), "2026-01-02 22:00:00");
+        // 新加入的任务不记录加入之前的触发点
+        let later = eval(&config, &state, "2026-01-03 12:00:00");
+        assert_eq!(later.state, DueState::Pending);
+        assert_eq!(trigger(&later), "2026-01-09 22:00:00");
+        assert!(later.skipped.is_empty());
+    }
+
+    #[test]
+    fn schedule_count_from_start() {
+        let config = task(json!({"schedule": {
+            "cron": "0 22 * * 5",
+            "start": "2026-01-01 00:00:00",
+            "count": 2,
+        }}));
+        let mut state = StateStore::default();
+        for now in ["2026-01-02 22:05:00", "2026-01-09 22:05:00"] {
+            let due = eval(&config, &state, now);
+            assert_eq!(due.state, DueState::Due, "{}", now);
+            state.record(&config, &due);
+        }
+        let done = eval(&config, &state, "2026-01-09 22:10:00");
+        assert_eq!(done.state, DueState::AlreadyExecuted);
+        assert_eq!(trigger(&done), "2026-01-09 22:00:00");
+        let ended = eval(&config, &state, "2026-01-16 22:05:00");
+        assert_eq!(ended.state, DueState::Expired);
+        assert!(ended.skipped.is_empty());
+    }
+
+    #[test]
+    fn schedule_start_and_end() {
+        let config = task(json!({"schedule": {
+            "cron": "0 22 * * 5",
+            "start": "2026-01-05 00:00:00",
+            "end": "2026-01-10 00:00:00",
+        }}));
+        let state = StateStore::default();
+        let before = eval(&config, &state, "2026-01-02 22:05:00");
+        assert_eq!(before.state, DueState::Pending);
+        assert_eq!(trigger(&before), "2026-01-09 22:00:00");
+        assert_eq!(
+            eval(&config, &state, "2026-01-09 22:05:00").state,
+            DueState::Due
+        );
+        let after = eval(&config, &state, "2026-01-16 22:05:00");
+        assert_eq!(after.state, DueState::Expired);
+        assert_eq!(after.message.as_deref(), Some("周期计划已结束"));
+    }
+
+    #[test]
+    fn schedule_count_requires_start() {
+        let config = task(json!({"schedule": {"cron": "0 22 * * 5", "count": 2}}));
+        let due = eval(&config, &StateStore::default(), "2026-01-02 22:05:00");
+        assert_eq!(due.state, DueState::Invalid);
+    }
+
+    #[test]
+    fn once_misfire_policies() {
+        let config = |policy: serde_json::Value| {
+            task(json!({"trigger_time": "2026-01-02 22:00:00", "misfire_policy": policy}))
+        };
+        let state = StateStore::default();
+        let late = "2026-01-05 22:00:00";
+        assert_eq!(
+            eval(&config(json!("skip")), &state, late).state,
+            DueState::Expired
+        );
+        assert_eq!(
+            eval(&config(json!("fire_once")), &state, late).state,
+            DueState::Due
+        );
+        assert_eq!(
+            eval(&config(json!("fire_all")), &state, late).state,
+            DueState::Due
+        );
+        // 补发最多回溯 MAX_CATCHUP
+        let too_late = eval(&config(json!("fire_once")), &state, "2026-01-09 22:00:01");
+        assert_eq!(too_late.state, DueState::Expired);
+
+        let within = config(json!({"fire_if_within": "6h"}));
+        assert_eq!(
+            eval(&within, &state, "2026-01-03 04:00:00").state,
+            DueState::Due
+        );
+        let expired = eval(&within, &state, "2026-01-03 04:00:01");
+        assert_eq!(expired.state, DueState::Expired);
+        assert_eq!(local(&expired.skipped), ["2026-01-02 22:00:00"]);
+    }
+
+    #[test]
+    fn schedule_misfire_policies() {
+        let config = |policy: &str| {
+            task(json!({"schedule": {"cron": "0 22 * * 5"}, "misfire_policy": policy}))
+        };
+        // 01-02 已执行, 01-09 错过, 01-16 尚未到
+        let history = |config: &TaskConfig| {
+            let mut state = StateStore::default();
+            let first = eval(config, &state, "2026-01-02 22:05:00");
+            state.record(config, &first);
+            state
+        };
+        let now = "2026-01-16 21:00:00";
+
+        let skip = config("skip");
+        let due = eval(&skip, &history(&skip), now);
+        assert_eq!(due.state, DueState::Pending);
+        assert_eq!(trigger(&due), "2026-01-16 22:00:00");
+        assert_eq!(local(&due.skipped), ["2026-01-09 22:00:00"]);
+
+        for policy in ["fire_once", "fire_all"] {
+            let config = config(policy);
+            let due = eval(&config, &history(&config), now);
+            assert_eq!(due.state, DueState::Due, "{}", policy);
+            assert_eq!(trigger(&due), "2026-01-09 22:00:00");
+            assert!(due.skipped.is_empty());
+        }
+    }
+
+    #[test]
+    fn schedule_fire_once_keeps_latest_and_fire_all_keeps_earliest() {
+        let config = |policy: &str| {
+            task(json!({"schedule": {"cron": "0 22 * * *"}, "misfire_policy": policy}))
+        };
+        let history = |config: &TaskConfig| {
+            let mut state = StateStore::default();
+            let first = eval(config, &state, "2026-01-01 22:05:00");
+            state.record(config, &first);
+            state
+        };
+        // 01-02 至 01-04 三个触发点都已错过
+        let now = "2026-01-05 12:00:00";
+
+        let once = config("fire_once");
+        let due = eval(&once, &history(&once), now);
+        assert_eq!(trigger(&due), "2026-01-04 22:00:00");
+        assert_eq!(
+            local(&due.skipped),
+            ["2026-01-02 22:00:00", "2026-01-03 22:00:00"]
+        );
+
+        let all = config("fire_all");
+        let mut state = history(&all);
+        let due = eval(&all, &state, now);
+        assert_eq!(trigger(&due), "2026-01-02 22:00:00");
+        assert!(due.skipped.is_empty());
+        state.record(&all, &due);
+        assert_eq!(trigger(&eval(&all, &state, now)), "2026-01-03 22:00:00");
+    }
+
+    #[test]
+    fn schedule_records_occurrences_older_than_catchup_as_skipped() {
+        // 2026-01-02 为周五
+        let config = task(json!({"schedule": {"cron": "0 22 * * 5"}}));
+        let mut state = StateStore::default();
+        let first = eval(&config, &state, "2026-01-02 22:05:00");
+        assert_eq!(first.state, DueState::Due);
+        state.record(&config, &first);
+
+        let due = eval(&config, &state, "2026-01-16 22:05:00");
+        assert_eq!(due.state, DueState::Due);
+        assert_eq!(trigger(&due), "2026-01-16 22:00:00");
+        assert_eq!(local(&due.skipped), ["2026-01-09 22:00:00"]);
+
+        state.record(&config, &due);
+        let again = eval(&config, &state, "2026-01-16 22:10:00");
+        assert!(again.skipped.is_empty());
+    }
+}
@@ -0,0 +1,256 @@
+use pyo3::prelude::*;
+use pyo3::sync::GILOnceCell;
+use pyo3::types::PyDict;
+use std::io;
+
+// 异常需要同时继承 TaskIoError 与对应的内置异常 (兼容原先的 except OSError / ValueError 等),
+// create_exception! 只支持单一基类, 因此用 Python 代码定义
+const CLASSES: &str = r#"
+class TaskIoError(Exception):
+    """task_io 所有异常的基类"""
+
+class ConfigReadError(TaskIoError, OSError):
+    """读取配置、状态或历史文件失败; path 为文件路径"""
+
+class ConfigNotFound(ConfigReadError, FileNotFoundError):
+    """文件不存在; path 为文件路径"""
+
+class ConfigParseError(TaskIoError, ValueError):
+    """配置格式错误; path 为文件路径, line / column 从 1 开始, field 为出错的字段路径 (可能为 None)"""
+
+class ConfigWriteError(TaskIoError, OSError):
+    """写入配置或状态文件失败; path 为文件路径"""
+
+class RequestConfigError(TaskIoError, ValueError):
+    """请求配置有误, 如引用的环境变量未设置或代理地址无效"""
+
+class UnsupportedMethod(TaskIoError, ValueError):
+    """不支持的 HTTP 方法; method 为方法名"""
+
+class HttpClientError(TaskIoError, RuntimeError):
+    """构建 HTTP Client 失败"""
+
+class HttpError(TaskIoError, ConnectionError):
+    """请求发送失败; url 为请求地址 (可能为 None), kind 为错误类别 (同执行历史中的 error_kind)"""
+
+class HttpTimeout(HttpError, TimeoutError):
+    """请求超时"""
+
+class HttpConnectError(HttpError):
+    """无法建立连接"""
+
+class HttpTlsError(HttpError):
+    """TLS 握手或证书校验失败"""
+"#;
+
+/// task_io 抛出的异常类别, 对应 CLASSES 中的同名 Python 类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    TaskIoError,
+    ConfigReadError,
+    ConfigNotFound,
+    ConfigParseError,
+    ConfigWriteError,
+    RequestConfigError,
+    UnsupportedMethod,
+    HttpClientError,
+    HttpError,
+    HttpTimeout,
+    HttpConnectError,
+    HttpTlsError,
+}
+
+impl ErrorClass {
+    pub const ALL: [ErrorClass; 12] = [
+        ErrorClass::TaskIoError,
+        ErrorClass::ConfigReadError,
+        ErrorClass::ConfigNotFound,
+        ErrorClass::ConfigParseError,
+        ErrorClass::ConfigWriteError,
+        ErrorClass::RequestConfigError,
+        ErrorClass::UnsupportedMethod,
+        ErrorClass::HttpClientError,
+        ErrorClass::HttpError,
+        ErrorClass::HttpTimeout,
+        ErrorClass::HttpConnectError,
+        ErrorClass::HttpTlsError,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorClass::TaskIoError => "TaskIoError",
+            ErrorClass::ConfigReadError => "ConfigReadError",
+            ErrorClass::ConfigNotFound => "ConfigNotFound",
+            ErrorClass::ConfigParseError => "ConfigParseError",
+            ErrorClass::ConfigWriteError => "ConfigWriteError",
+            ErrorClass::RequestConfigError => "RequestConfigError",
+            ErrorClass::UnsupportedMethod => "UnsupportedMethod",
+            ErrorClass::HttpClientError => "HttpClientError",
+            ErrorClass::HttpError => "HttpError",
+            ErrorClass::HttpTimeout => "HttpTimeout",
+            ErrorClass::HttpConnectError => "HttpConnectError",
+            ErrorClass::HttpTlsError => "HttpTlsError",
+        }
+    }
+
+    /// 对应的 Python 异常类
+    pub fn type_object<'py>(self, py: Python<'py>) -> PyResult<&'py PyAny> {
+        static CLASSES_DICT: GILOnceCell<Py<PyDict>> = GILOnceCell::new();
+        let classes = CLASSES_DICT.get_or_try_init(py, || -> PyResult<_> {
+            let globals = PyDict::new(py);
+            // 类的 __module__ 取自 __name__
+            globals.set_item("__name__", "task_io")?;
+            py.run(CLASSES, Some(globals), None)?;
+            Ok(globals.into())
+        })?;
+        Ok(classes
+            .as_ref(py)
+            .get_item(self.name())?
+            .expect("异常类已定义"))
+    }
+}
+
+/// 创建异常并设置结构化属性; 需要持有 GIL
+pub fn new_error(
+    py: Python,
+    class: ErrorClass,
+    message: String,
+    attrs: &[(&str, PyObject)],
+) -> PyErr {
+    let build = || -> PyResult<PyErr> {
+        let value = class.type_object(py)?.call1((message,))?;
+        for (name, attr) in attrs {
+            value.setattr(*name, attr)?;
+        }
+        Ok(PyErr::from_value(value))
+    };
+    build().unwrap_or_else(|e| e)
+}
+
+/// 读取文件失败: 文件不存在时为 ConfigNotFound, 否则为 ConfigReadError
+///
+/// 除 path 外也设置 OSError 的 errno (不设置 filename, 否则 str() 会丢掉 message)。
+pub fn read_error(py: Python, path: &str, message: String, e: &io::Error) -> PyErr {
+    let class = if e.kind() == io::ErrorKind::NotFound {
+        ErrorClass::ConfigNotFound
+    } else {
+        ErrorClass::ConfigReadError
+    };
+    new_error(py, class, message, &os_attrs(py, path, e))
+}
+
+/// 写入文件失败 (ConfigWriteError)
+pub fn write_error(py: Python, path: &str, message: String, e: &io::Error) -> PyErr {
+    new_error(
+        py,
+        ErrorClass::ConfigWriteError,
+        message,
+        &os_attrs(py, path, e),
+    )
+}
+
+/// JSON 解析失败 (ConfigParseError); field 为出错的字段路径
+pub fn parse_error(
+    py: Python,
+    path: &str,
+    message: String,
+    e: &serde_json::Error,
+    field: Option<String>,
+) -> PyErr {
+    new_error(
+        py,
+        ErrorClass::ConfigParseError,
+        message,
+        &[
+            ("path", path.into_py(py)),
+            ("line", e.line().into_py(py)),
+            ("column", e.column().into_py(py)),
+            ("field", field.into_py(py)),
+        ],
+    )
+}
+
+fn os_attrs(py: Python, path: &str, e: &io::Error) -> [(&'static str, PyObject); 2] {
+    [
+        ("path", path.into_py(py)),
+        ("errno", e.raw_os_error().into_py(py)),
+    ]
+}
+
+/// 把异常类注册到模块
+pub fn register(py: Python, m: &PyModule) -> PyResult<()> {
+    for class in ErrorClass::ALL {
+        m.add(class.name(), class.type_object(py)?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::{PyModule, PyType};
+
+    #[test]
+    fn registers_documented_hierarchy() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let m = PyModule::new(py, "task_io").unwrap();
+            register(py, m).unwrap();
+            let builtins = py.import("builtins").unwrap();
+            let class = |name: &str| -> &PyType {
+                m.getattr(name)
+                    .or_else(|_| builtins.getattr(name))
+                    .unwrap()
+                    .downcast()
+                    .unwrap()
+            };
+            for c in ErrorClass::ALL {
+                let t = class(c.name());
+                assert!(t.is_subclass(class("TaskIoError")).unwrap(), "{}", c.name());
+                let module: String = t.getattr("__module__").unwrap().extract().unwrap();
+                assert_eq!(module, "task_io");
+            }
+            let parents: &[(&str, &[&str])] = &[
+                ("ConfigReadError", &["OSError"]),
+                ("ConfigNotFound", &["ConfigReadError", "FileNotFoundError"]),
+                ("ConfigParseError", &["ValueError"]),
+                ("ConfigWriteError", &["OSError"]),
+                ("RequestConfigError", &["ValueError"]),
+                ("UnsupportedMethod", &["ValueError"]),
+                ("HttpClientError", &["RuntimeError"]),
+                ("HttpError", &["ConnectionError"]),
+                ("HttpTimeout", &["HttpError", "TimeoutError"]),
+                ("HttpConnectError", &["HttpError"]),
+                ("HttpTlsError", &["HttpError"]),
+            ];
+            for (child, bases) in parents {
+                for base in *bases {
+                    let ok = class(child).is_subclass(class(base)).unwrap();
+                    assert!(ok, "{} 应继承 {}", child, base);
+                }
+            }
+            assert!(!class("HttpError").is_subclass(class("ValueError")).unwrap());
+        });
+    }
+
+    #[test]
+    fn read_error_sets_attributes() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let missing = io::Error::from(io::ErrorKind::NotFound);
+            let err = read_error(py, "a.json", "读取失败 a.json".to_string(), &missing);
+            let value = err.value(py);
+            assert_eq!(value.get_type().name().unwrap(), "ConfigNotFound");
+            assert_eq!(value.str().unwrap().to_str().unwrap(), "读取失败 a.json");
+            let path: String = value.getattr("path").unwrap().extract().unwrap();
+            assert_eq!(path, "a.json");
+
+            let denied = io::Error::from_raw_os_error(13);
+            let err = read_error(py, "a.json", "读取失败".to_string(), &denied);
+            let value = err.value(py);
+            assert_eq!(value.get_type().name().unwrap(), "ConfigReadError");
+            let errno: i32 = value.getattr("errno").unwrap().extract().unwrap();
+            assert_eq!(errno, 13);
+        });
+    }
+}
@@ -0,0 +1,223 @@
+use crate::response::Response;
+use crate::retry::StatusPattern;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// 判定请求是否成功的断言 (任务配置中的 `expect` 字段, 所有字段均可省略)
+///
+/// ```json
+/// "expect": {
+///   "status": [200],
+///   "json": { "$.code": 200, "/data/ok": true },
+///   "body_matches": "\"code\":\\s*200",
+///   "headers": ["X-Request-Id"]
+/// }
+/// ```
+///
+/// 未配置 `expect` 时与原先一致, 2xx 即成功。
+/// Bark 等接口在逻辑失败时也返回 200, 需要用 json 断言检查响应中的 code。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Expect {
+    /// 允许的状态码, 为空时为 2xx
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub status: Vec<StatusPattern>,
+    /// 路径 (JSON Pointer 或 `$.a.b[0]`) -> 期望值
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub json: Map<String, Value>,
+    /// 响应体需要匹配的正则
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_matches: Option<String>,
+    /// 必须存在的响应头
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<String>,
+}
+
+impl Expect {
+    /// 检查路径与正则是否合法, 供配置校验使用
+    pub fn check_config(&self) -> Result<(), String> {
+        for path in self.json.keys() {
+            to_pointer(path)?;
+        }
+        if let Some(pattern) = &self.body_matches {
+            Regex::new(pattern).map_err(|e| format!("body_matches 正则无效: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// 依次检查各项断言, 返回第一条不满足的原因
+    pub fn check(&self, response: &Response) -> Result<(), String> {
+        let status_ok = if self.status.is_empty() {
+            response.is_success()
+        } else {
+            self.status.iter().any(|p| p.matches(response.status))
+        };
+        if !status_ok {
+            let allowed = if self.status.is_empty() {
+                "2xx".to_string()
+            } else {
+                self.status
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            return Err(format!(
+                "状态码 {} 不在期望范围内 ({})",
+                response.status, allowed
+            ));
+        }
+
+        for name in &self.headers {
+            if response.header(name).is_none() {
+                return Err(format!("缺少响应头 {}", name));
+            }
+        }
+
+        if !self.json.is_empty() {
+            let body: Value = serde_json::from_slice(&response.body)
+                .map_err(|e| format!("响应不是合法的 JSON: {}", e))?;
+            for (path, expected) in &self.json {
+                match body.pointer(&to_pointer(path)?) {
+                    Some(actual) if json_eq(actual, expected) => {}
+                    Some(actual) => {
+                        return Err(format!("{} 为 {}, 期望 {}", path, actual, expected))
+                    }
+                    None => return Err(format!("{} 不存在, 期望 {}", path, expected)),
+                }
+            }
+        }
+
+        if let Some(pattern) = &self.body_matches {
+            let re = Regex::new(pattern).map_err(|e| format!("body_matches 正则无效: {}", e))?;
+            if !re.is_match(&response.text) {
+                return Err(format!("响应内容不匹配 {}", pattern));
+            }
+        }
+        Ok(())
+    }
+}
+
+// 200 与 200.0 视为相等
+fn json_eq(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Number(a), Value::Number(b)) => a == b || a.as_f64() == b.as_f64(),
+        _ => actual == expected,
+    }
+}
+
+/// 把 `$.data.items[0].name` 形式的路径转为 JSON Pointer, 以 `/` 开头的路径原样返回
+pub fn to_pointer(path: &str) -> Result<String, String> {
+    if path.is_empty() || path.starts_with('/') {
+        return Ok(path.to_string());
+    }
+    let err = || {
+        format!(
+            "路径格式错误 {}: 应为 JSON Pointer (/a/b) 或 $.a.b[0]",
+            path
+        )
+    };
+    let mut rest = path.strip_prefix('$').ok_or_else(err)?;
+    let mut pointer = String::new();
+    while !rest.is_empty() {
+        let segment;
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            segment = &after[..end];
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or_else(err)?;
+            segment = after[..end].trim_matches(|c| c == '\'' || c == '"');
+            rest = &after[end + 1..];
+        } else {
+            return Err(err());
+        }
+        if segment.is_empty() {
+            return Err(err());
+        }
+        pointer.push('/');
+        pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+    }
+    Ok(pointer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderMap;
+    use serde_json::json;
+
+    fn response(status: u16, text: &str) -> Response {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "1".parse().unwrap());
+        Response {
+            status,
+            headers,
+            url: "https://example.com/hook".to_string(),
+            elapsed: std::time::Duration::ZERO,
+            redirects: 0,
+            body: text.as_bytes().to_vec(),
+            text: text.to_string(),
+            journal_error: None,
+        }
+    }
+
+    fn expect(value: Value) -> Expect {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn to_pointer_converts_dotted_paths() {
+        assert_eq!(to_pointer("$.code").unwrap(), "/code");
+        assert_eq!(
+            to_pointer("$.data.items[0].name").unwrap(),
+            "/data/items/0/name"
+        );
+        assert_eq!(to_pointer("$['a.b'][\"c\"]").unwrap(), "/a.b/c");
+        assert_eq!(to_pointer("$.a/b.c~d").unwrap(), "/a~1b/c~0d");
+        assert_eq!(to_pointer("$").unwrap(), "");
+        assert_eq!(to_pointer("/data/ok").unwrap(), "/data/ok");
+        assert_eq!(to_pointer("").unwrap(), "");
+        for bad in ["code", "$code", "$.", "$.a..b", "$[0", "$[]"] {
+            assert!(to_pointer(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn check_json_status_headers_and_body() {
+        let rule = expect(json!({
+            "status": [200],
+            "json": {"$.code": 200, "/data/ok": true},
+            "body_matches": "\"code\":\\s*200",
+            "headers": ["X-Request-Id"],
+        }));
+        assert_eq!(
+            rule.check(&response(200, r#"{"code": 200.0, "data": {"ok": true}}"#)),
+            Ok(())
+        );
+        assert!(rule
+            .check(&response(200, r#"{"code": 400, "data": {"ok": true}}"#))
+            .unwrap_err()
+            .contains("$.code"));
+        assert!(rule
+            .check(&response(201, r#"{"code": 200}"#))
+            .unwrap_err()
+            .contains("201"));
+        assert!(expect(json!({"headers": ["X-Missing"]}))
+            .check(&response(200, ""))
+            .unwrap_err()
+            .contains("X-Missing"));
+        assert!(rule.check(&response(200, "not json")).is_err());
+    }
+
+    #[test]
+    fn default_expect_is_2xx() {
+        assert_eq!(Expect::default().check(&response(204, "")), Ok(()));
+        assert!(Expect::default().check(&response(302, "")).is_err());
+        assert!(expect(json!({"json": {"bad path": 1}}))
+            .check_config()
+            .is_err());
+        assert!(expect(json!({"body_matches": "("})).check_config().is_err());
+    }
+}
@@ -0,0 +1,339 @@
+use crate::errors;
+use crate::http::RequestError;
+use chrono::{SecondsFormat, Utc};
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// 默认的执行历史文件 (JSON Lines, 只追加)
+pub const DEFAULT_HISTORY_PATH: &str = "state/history.jsonl";
+
+/// 历史中保留的响应内容长度 (字符)
+const MAX_RESPONSE_CHARS: usize = 500;
+
+/// 一次请求尝试的记录
+#[pyclass]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AttemptRecord {
+    #[pyo3(get)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    #[pyo3(get)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_name: Option<String>,
+    /// 尝试开始的时间 (UTC, RFC 3339)
+    #[pyo3(get)]
+    pub timestamp: String,
+    /// 第几次尝试, 从 1 开始
+    #[pyo3(get)]
+    pub attempt: u32,
+    #[pyo3(get)]
+    pub method: String,
+    #[pyo3(get)]
+    pub url: String,
+    #[pyo3(get)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[pyo3(get)]
+    pub latency_ms: u64,
+    /// 截断后的响应内容
+    #[pyo3(get)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+    /// 请求失败时的错误类别, 如 timeout / dns / tls (见 RequestError::kind)
+    #[pyo3(get)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<String>,
+    #[pyo3(get)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 配置了 expect 时, 响应是否满足断言
+    #[pyo3(get)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accepted: Option<bool>,
+    /// 写入执行历史失败的原因; 只返回给调用方, 不写入历史
+    #[pyo3(get)]
+    #[serde(skip)]
+    pub journal_error: Option<String>,
+}
+
+impl AttemptRecord {
+    pub fn new(method: &str, url: &str, attempt: u32) -> Self {
+        AttemptRecord {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            attempt,
+            method: method.to_uppercase(),
+            url: url.to_string(),
+            ..AttemptRecord::default()
+        }
+    }
+
+    /// 记录响应内容, 超过 MAX_RESPONSE_CHARS 时截断
+    pub fn set_response(&mut self, text: &str) {
+        let mut truncated: String = text.chars().take(MAX_RESPONSE_CHARS).collect();
+        if truncated.len() < text.len() {
+            truncated.push('…');
+        }
+        self.response = Some(truncated);
+    }
+
+    /// 记录 expect 断言的结果, 不满足时原因记为 assertion 错误
+    pub fn set_accepted(&mut self, result: Result<(), String>) {
+        self.accepted = Some(result.is_ok());
+        if let Err(reason) = result {
+            self.error_kind = Some("assertion".to_string());
+            self.error = Some(reason);
+        }
+    }
+
+    /// 记录请求失败的原因
+    pub fn set_error(&mut self, e: &RequestError) {
+        self.error_kind = Some(e.kind().to_string());
+        self.error = Some(e.to_string());
+    }
+
+    /// 失败原因的类别: 请求失败时为 error_kind, 收到非 2xx 响应时为 `http_<状态码>`; 成功时为 None
+    pub fn failure_cause(&self) -> Option<String> {
+        if self.ok() {
+            return None;
+        }
+        match (&self.error_kind, self.status) {
+            (Some(kind), _) => Some(kind.clone()),
+            (None, Some(status)) => Some(format!("http_{}", status)),
+            (None, None) => Some("unknown".to_string()),
+        }
+    }
+}
+
+/// 按失败原因统计失败的尝试次数
+pub fn failure_counts(records: &[AttemptRecord]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for cause in records.iter().filter_map(AttemptRecord::failure_cause) {
+        *counts.entry(cause).or_insert(0) += 1;
+    }
+    counts
+}
+
+#[pymethods]
+impl AttemptRecord {
+    /// 请求是否成功: 配置了 expect 时看断言结果, 否则看是否收到 2xx 响应
+    #[getter]
+    pub fn ok(&self) -> bool {
+        self.accepted
+            .unwrap_or_else(|| self.status.is_some_and(|s| (200..300).contains(&s)))
+    }
+
+    fn __repr__(&self) -> String {
+        let outcome = match (self.status, &self.error_kind) {
+            (Some(status), _) => format!("status={}", status),
+            (None, Some(kind)) => format!("error_kind='{}'", kind),
+            (None, None) => "status=None".to_string(),
+        };
+        format!(
+            "AttemptRecord(task_name='{}', timestamp='{}', attempt={}, {}, latency_ms={})",
+            self.task_name.as_deref().unwrap_or_default(),
+            self.timestamp,
+            self.attempt,
+            outcome,
+            self.latency_ms
+        )
+    }
+}
+
+/// 向历史文件追加一条记录
+pub fn append(path: &Path, record: &AttemptRecord) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        if !dir.as_os_str().is_empty() {
+            fs::create_dir_all(dir)?;
+        }
+    }
+    let mut line =
+        serde_json::to_string(record).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    line.push('\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)?;
+    // 上次写入被中断时文件末尾没有换行, 先补上, 避免新记录接在残缺的行后面
+    if file.metadata()?.len() > 0 {
+        let mut last = [0];
+        file.seek(SeekFrom::End(-1))?;
+        file.read_exact(&mut last)?;
+        if last[0] != b'\n' {
+            line.insert(0, '\n');
+        }
+    }
+    // 整行一次写入, 进程中途被杀最多留下一行残缺记录, 读取时会跳过
+    file.write_all(line.as_bytes())?;
+    file.sync_data()
+}
+
+/// 追加一条记录; 请求已经发出, 写入失败不影响结果, 原因记在 `record.journal_error` 中
+pub fn journal(path: &Path, record: &mut AttemptRecord) {
+    if let Err(e) = append(path, record) {
+        record.journal_error = Some(format!("写入执行历史失败 {}: {}", path.display(), e));
+    }
+}
+
+/// 读取历史记录 (按时间先后), 文件不存在时返回空列表
+///
+/// `task_id` 为 Some 时只返回该任务的记录, `limit` 只保留最近的若干条。
+/// 残缺或无法解析的行 (包括非 UTF-8 内容) 会被跳过。
+pub fn read(
+    path: &Path,
+    task_id: Option<&str>,
+    limit: Option<usize>,
+) -> io::Result<Vec<AttemptRecord>> {
+    let file = match fs::File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut records = Vec::new();
+    for line in BufReader::new(file).split(b'\n') {
+        let Ok(record) = serde_json::from_slice::<AttemptRecord>(&line?) else {
+            continue;
+        };
+        if task_id.is_none() || record.task_id.as_deref() == task_id {
+            records.push(record);
+        }
+    }
+    if let Some(limit) = limit {
+        let skip = records.len().saturating_sub(limit);
+        records.drain(..skip);
+    }
+    Ok(records)
+}
+
+// 查询执行历史 (按时间先后); task_id 为 None 时返回全部, limit 只保留最近的若干条
+#[pyfunction]
+#[pyo3(signature = (path, task_id=None, limit=None))]
+pub fn history(
+    path: String,
+    task_id: Option<String>,
+    limit: Option<usize>,
+    py: Python,
+) -> PyResult<Vec<AttemptRecord>> {
+    read_recent(py, &path, task_id.as_deref(), limit)
+}
+
+// 按失败原因 (timeout、dns、tls、http_503 等) 统计失败的尝试次数, 参数同 history
+#[pyfunction]
+#[pyo3(signature = (path, task_id=None, limit=None))]
+pub fn failure_summary(
+    path: String,
+    task_id: Option<String>,
+    limit: Option<usize>,
+    py: Python,
+) -> PyResult<BTreeMap<String, usize>> {
+    let records = read_recent(py, &path, task_id.as_deref(), limit)?;
+    Ok(failure_counts(&records))
+}
+
+fn read_recent(
+    py: Python,
+    path: &str,
+    task_id: Option<&str>,
+    limit: Option<usize>,
+) -> PyResult<Vec<AttemptRecord>> {
+    py.allow_threads(|| read(Path::new(path), task_id, limit))
+        .map_err(|e| errors::read_error(py, path, format!("读取历史失败 {}: {}", path, e), &e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("task_io_history_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("state").join("history.jsonl")
+    }
+
+    fn record(
+        task_id: &str,
+        attempt: u32,
+        status: Option<u16>,
+        error_kind: Option<&str>,
+    ) -> AttemptRecord {
+        AttemptRecord {
+            task_id: Some(task_id.to_string()),
+            status,
+            error_kind: error_kind.map(str::to_string),
+            ..AttemptRecord::new("post", "https://example.com/hook", attempt)
+        }
+    }
+
+    fn attempts(records: &[AttemptRecord]) -> Vec<(String, u32)> {
+        records
+            .iter()
+            .map(|r| (r.task_id.clone().unwrap_or_default(), r.attempt))
+            .collect()
+    }
+
+    #[test]
+    fn append_and_read_with_filter_and_limit() {
+        let path = temp_file("filter");
+        assert!(read(&path, None, None).unwrap().is_empty());
+        for (task, attempt) in [("a", 1), ("b", 1), ("a", 2), ("a", 3)] {
+            append(&path, &record(task, attempt, Some(200), None)).unwrap();
+        }
+
+        let all = read(&path, None, None).unwrap();
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[0].method, "POST");
+        let a = attempts(&read(&path, Some("a"), None).unwrap());
+        assert_eq!(a, [("a".into(), 1), ("a".into(), 2), ("a".into(), 3)]);
+        let recent = attempts(&read(&path, Some("a"), Some(2)).unwrap());
+        assert_eq!(recent, [("a".into(), 2), ("a".into(), 3)]);
+        assert_eq!(read(&path, Some("c"), None).unwrap().len(), 0);
+        assert_eq!(read(&path, None, Some(10)).unwrap().len(), 4);
+        fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn skips_damaged_lines_and_repairs_missing_newline() {
+        let path = temp_file("damaged");
+        append(&path, &record("a", 1, Some(200), None)).unwrap();
+        // 非 UTF-8 的行、非 JSON 的行, 以及被中断、没有换行的半行
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"\xff\xfe{}\nnot json\n{\"task_id\": \"a\", \"times")
+            .unwrap();
+        drop(file);
+
+        append(&path, &record("a", 2, Some(200), None)).unwrap();
+        let records = read(&path, None, None).unwrap();
+        assert_eq!(attempts(&records), [("a".into(), 1), ("a".into(), 2)]);
+        assert!(fs::read(&path).unwrap().ends_with(b"}\n"));
+        fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn counts_failures_by_cause() {
+        let mut rejected = record("a", 1, Some(200), None);
+        rejected.set_accepted(Err("状态码不符".to_string()));
+        let records = [
+            record("a", 1, Some(200), None),
+            record("a", 2, Some(503), None),
+            record("a", 3, Some(503), None),
+            record("a", 4, None, Some("timeout")),
+            record("a", 5, None, None),
+            rejected,
+        ];
+        let counts = failure_counts(&records);
+        let expected: BTreeMap<String, usize> = [
+            ("assertion".to_string(), 1),
+            ("http_503".to_string(), 2),
+            ("timeout".to_string(), 1),
+            ("unknown".to_string(), 1),
+        ]
+        .into();
+        assert_eq!(counts, expected);
+    }
+}
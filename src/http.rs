@@ -0,0 +1,561 @@
+use crate::auth::{resolve_headers, Auth};
+use crate::body::BodyType;
+use crate::errors::{new_error, ErrorClass};
+use crate::expect::Expect;
+use crate::response::Response;
+use pyo3::prelude::*;
+use reqwest::blocking::RequestBuilder;
+use reqwest::header::LOCATION;
+use reqwest::Method;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// send 的错误
+#[derive(Debug)]
+pub enum RequestError {
+    /// 构建 Client 失败
+    Client(reqwest::Error),
+    UnsupportedMethod(String),
+    /// 请求配置有误 (如引用的环境变量未设置)
+    Config(String),
+    /// Location 无效
+    Redirect(String),
+    /// 重定向次数超过上限 (通常是循环重定向)
+    TooManyRedirects(u32),
+    /// 请求发送失败 (超时、连接失败等)
+    Send(reqwest::Error),
+}
+
+impl RequestError {
+    /// 机器可读的错误类别, 写入执行历史
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RequestError::Client(_) => "client",
+            RequestError::UnsupportedMethod(_) => "unsupported_method",
+            RequestError::Config(_) => "config",
+            RequestError::Redirect(_) => "redirect",
+            RequestError::TooManyRedirects(_) => "redirect_loop",
+            RequestError::Send(e) if e.is_timeout() => "timeout",
+            RequestError::Send(e) if is_tls(e) => "tls",
+            RequestError::Send(e) if is_dns(e) => "dns",
+            RequestError::Send(e) if is_refused(e) => "refused",
+            RequestError::Send(e) if e.is_connect() => "connect",
+            RequestError::Send(e) if e.is_redirect() => "redirect",
+            RequestError::Send(e) if e.is_body() || e.is_decode() => "body",
+            RequestError::Send(_) => "request",
+        }
+    }
+
+    /// 网络层面的失败 (超时、连接失败等), 是否重试由 RetryPolicy::retry_on_errors 决定
+    pub fn is_network(&self) -> bool {
+        matches!(self, RequestError::Send(e) if !e.is_builder())
+    }
+}
+
+/// 网络层面的错误类别 (RequestError::kind 的取值), 可写入 retry_on_errors
+pub const NETWORK_ERROR_KINDS: &[&str] = &[
+    "timeout", "dns", "refused", "connect", "tls", "body", "request",
+];
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::Client(e) => write!(f, "构建 Client 失败: {}", e),
+            RequestError::UnsupportedMethod(m) => write!(f, "不支持的方法: {}", m),
+            RequestError::Config(msg) => write!(f, "请求配置错误: {}", msg),
+            RequestError::Redirect(msg) => write!(f, "重定向失败: {}", msg),
+            RequestError::TooManyRedirects(max) => {
+                write!(f, "重定向失败: 重定向次数超过 {}", max)
+            }
+            RequestError::Send(e) => write!(f, "网络请求失败: {}", e),
+        }
+    }
+}
+
+impl RequestError {
+    /// 对应的 Python 异常类 (task_io.TaskIoError 的子类)
+    pub fn error_class(&self) -> ErrorClass {
+        match self {
+            RequestError::Client(_) => ErrorClass::HttpClientError,
+            RequestError::UnsupportedMethod(_) => ErrorClass::UnsupportedMethod,
+            RequestError::Config(_) => ErrorClass::RequestConfigError,
+            RequestError::Redirect(_) | RequestError::TooManyRedirects(_) => ErrorClass::HttpError,
+            RequestError::Send(_) => match self.kind() {
+                "timeout" => ErrorClass::HttpTimeout,
+                "tls" => ErrorClass::HttpTlsError,
+                "dns" | "refused" | "connect" => ErrorClass::HttpConnectError,
+                _ => ErrorClass::HttpError,
+            },
+        }
+    }
+}
+
+// 发送相关的异常带 url 与 kind 属性, UnsupportedMethod 带 method 属性
+impl From<RequestError> for PyErr {
+    fn from(e: RequestError) -> PyErr {
+        Python::with_gil(|py| {
+            let attrs = match &e {
+                RequestError::UnsupportedMethod(method) => vec![("method", method.into_py(py))],
+                RequestError::Send(err) => vec![
+                    ("url", err.url().map(|u| u.to_string()).into_py(py)),
+                    ("kind", e.kind().into_py(py)),
+                ],
+                RequestError::Redirect(_) | RequestError::TooManyRedirects(_) => {
+                    vec![("url", py.None()), ("kind", e.kind().into_py(py))]
+                }
+                RequestError::Client(_) | RequestError::Config(_) => Vec::new(),
+            };
+            new_error(py, e.error_class(), e.to_string(), &attrs)
+        })
+    }
+}
+
+// 沿 source 链查找满足条件的错误
+fn any_source(e: &reqwest::Error, f: impl Fn(&(dyn std::error::Error + 'static)) -> bool) -> bool {
+    let mut source = std::error::Error::source(e);
+    while let Some(err) = source {
+        if f(err) {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
+// TLS 后端的错误 (证书校验失败、握手失败等)
+fn is_tls(e: &reqwest::Error) -> bool {
+    any_source(e, |err| err.is::<native_tls::Error>())
+}
+
+// 域名解析失败; hyper 的 ConnectError 不公开, 只能按其消息 "dns error" 判断
+fn is_dns(e: &reqwest::Error) -> bool {
+    any_source(e, |err| err.to_string().starts_with("dns error"))
+}
+
+// 对方拒绝连接 (端口未监听)
+fn is_refused(e: &reqwest::Error) -> bool {
+    any_source(e, |err| {
+        err.downcast_ref::<std::io::Error>()
+            .is_some_and(|io| io.kind() == std::io::ErrorKind::ConnectionRefused)
+    })
+}
+
+// reqwest 的错误信息带有完整 URL; auth.in 为 query 时其中含有 API Key,
+// 在写入执行历史或抛给 Python 之前去掉密码并隐藏查询参数的值
+fn send_error(e: reqwest::Error) -> RequestError {
+    match e.url().map(redact_url) {
+        Some(url) => RequestError::Send(e.with_url(url)),
+        None => RequestError::Send(e),
+    }
+}
+
+fn redact_url(url: &reqwest::Url) -> reqwest::Url {
+    let mut url = url.clone();
+    let _ = url.set_password(None);
+    if url.query().is_some() {
+        let names: Vec<String> = url
+            .query_pairs()
+            .map(|(name, _)| name.into_owned())
+            .collect();
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(names.iter().map(|name| (name, "***")));
+    }
+    url
+}
+
+/// 一次请求的全部参数, 重试时重复使用
+#[derive(Debug, Clone)]
+pub struct RequestSpec {
+    pub method: String,
+    pub url: String,
+    /// 请求体; 未设置 query 的 GET 请求中作为查询参数
+    pub payload: Value,
+    pub body_type: BodyType,
+    /// 查询参数
+    pub query: Option<Value>,
+    /// 已替换环境变量的请求头
+    pub headers: Vec<(String, String)>,
+    /// 已替换环境变量的认证信息
+    pub auth: Option<Auth>,
+    pub timeout_secs: u64,
+    /// 判定成功的断言, 为空时 2xx 即成功
+    pub expect: Option<Expect>,
+}
+
+impl RequestSpec {
+    pub fn new(method: &str, url: &str, payload: Value, timeout_secs: u64) -> Self {
+        RequestSpec {
+            method: method.to_string(),
+            url: url.to_string(),
+            payload,
+            body_type: BodyType::Json,
+            query: None,
+            headers: Vec::new(),
+            auth: None,
+            timeout_secs,
+            expect: None,
+        }
+    }
+
+    pub fn with_body_type(mut self, body_type: BodyType) -> Self {
+        self.body_type = body_type;
+        self
+    }
+
+    pub fn with_query(mut self, query: Option<Value>) -> Self {
+        self.query = query;
+        self
+    }
+
+    pub fn with_expect(mut self, expect: Option<Expect>) -> Self {
+        self.expect = expect;
+        self
+    }
+
+    /// 设置请求头与认证, 其中的 `${NAME}` 替换为环境变量
+    pub fn with_auth(
+        mut self,
+        headers: &BTreeMap<String, String>,
+        auth: Option<&Auth>,
+    ) -> Result<Self, RequestError> {
+        self.headers = resolve_headers(headers).map_err(RequestError::Config)?;
+        self.auth = match auth {
+            Some(a) => Some(a.resolve().map_err(RequestError::Config)?),
+            None => None,
+        };
+        Ok(self)
+    }
+}
+
+fn is_empty(payload: &Value) -> bool {
+    match payload {
+        Value::Null => true,
+        Value::Object(m) => m.is_empty(),
+        _ => false,
+    }
+}
+
+/// 最多跟随的重定向次数 (与 reqwest 默认一致)
+const MAX_REDIRECTS: u32 = 10;
+
+/// 跨域重定向时不再携带的请求头
+const SENSITIVE_HEADERS: &[&str] = &["authorization", "proxy-authorization", "cookie"];
+
+// 重定向链中的一跳
+struct Hop {
+    method: Method,
+    url: String,
+    /// 是否为第一跳; 只有第一跳附带查询参数
+    first: bool,
+    /// 303 等重定向改为 GET 后不再发送请求体
+    with_body: bool,
+    /// 跨域后不再发送认证信息
+    same_origin: bool,
+}
+
+/// 发送一次 HTTP 请求
+///
+/// 支持任意 HTTP 方法 (含 PATCH / HEAD / OPTIONS 及自定义方法)。
+/// query 为查询参数, payload 按 body_type 编码为请求体;
+/// 未设置 query 时 GET 请求把 payload 作为查询参数, 与旧版行为一致。
+/// 重定向由这里逐跳跟随, 以便统计次数; 规则与浏览器一致。
+/// client 需禁用自动重定向, 一般通过 HttpClient::send 调用。
+pub(crate) fn send(
+    client: &reqwest::blocking::Client,
+    spec: &RequestSpec,
+) -> Result<Response, RequestError> {
+    let method = Method::from_bytes(spec.method.to_uppercase().as_bytes())
+        .map_err(|_| RequestError::UnsupportedMethod(spec.method.clone()))?;
+    let mut hop = Hop {
+        method,
+        url: spec.url.clone(),
+        first: true,
+        with_body: true,
+        same_origin: true,
+    };
+    let started = Instant::now();
+    let mut redirects = 0;
+    loop {
+        // 1. 构建并发送请求
+        let response = build_request(client, spec, &hop)?
+            .send()
+            .map_err(send_error)?;
+
+        // 2. 跟随重定向, 或读取响应
+        let status = response.status().as_u16();
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|v| v.to_str().ok());
+        let location = match (status, location) {
+            (301 | 302 | 303 | 307 | 308, Some(location)) => location,
+            _ => return Response::read(response, started, redirects).map_err(send_error),
+        };
+        if redirects >= MAX_REDIRECTS {
+            return Err(RequestError::TooManyRedirects(MAX_REDIRECTS));
+        }
+        let next = response
+            .url()
+            .join(location)
+            .map_err(|e| RequestError::Redirect(format!("Location 无效 {}: {}", location, e)))?;
+        // 303 总是改为 GET; 301/302 的 POST 改为 GET; 307/308 保持方法与请求体
+        let to_get = status == 303 && hop.method != Method::HEAD
+            || matches!(status, 301 | 302) && hop.method == Method::POST;
+        if to_get {
+            hop.method = Method::GET;
+            hop.with_body = false;
+        }
+        hop.same_origin &= response.url().origin() == next.origin();
+        hop.url = next.to_string();
+        hop.first = false;
+        redirects += 1;
+    }
+}
+
+fn build_request(
+    client: &reqwest::blocking::Client,
+    spec: &RequestSpec,
+    hop: &Hop,
+) -> Result<RequestBuilder, RequestError> {
+    let payload = &spec.payload;
+    let mut request_builder = client
+        .request(hop.method.clone(), hop.url.as_str())
+        .timeout(Duration::from_secs(spec.timeout_secs));
+    // 未设置 query 的 GET 请求沿用旧规则，将 payload 作为 Query Params
+    let legacy_get = spec.method.eq_ignore_ascii_case("GET") && spec.query.is_none();
+    if hop.first {
+        if legacy_get {
+            request_builder = request_builder.query(payload);
+        } else if let Some(query) = &spec.query {
+            request_builder = request_builder.query(query);
+        }
+    }
+    // GET / HEAD / OPTIONS 的 body 为空时不发送请求体
+    let bodiless = matches!(hop.method, Method::GET | Method::HEAD | Method::OPTIONS);
+    let skip_body = !hop.with_body || legacy_get || (bodiless && is_empty(payload));
+    if !skip_body {
+        let has_content_type = spec
+            .headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("content-type"));
+        request_builder = spec
+            .body_type
+            .apply(request_builder, payload, has_content_type)?;
+    }
+    for (name, value) in &spec.headers {
+        if !hop.same_origin && SENSITIVE_HEADERS.contains(&name.to_lowercase().as_str()) {
+            continue;
+        }
+        request_builder = request_builder.header(name.as_str(), value.as_str());
+    }
+    if let (Some(auth), true) = (&spec.auth, hop.same_origin) {
+        request_builder = auth.apply(request_builder);
+    }
+    Ok(request_builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::HttpClient;
+    use crate::testing::{response, serve, Received};
+    use serde_json::json;
+    use std::io::Write;
+    use std::net::TcpListener;
+
+    fn send_to(spec: RequestSpec) -> Result<Response, RequestError> {
+        HttpClient::shared().send(&spec)
+    }
+
+    // 发送一次请求, 返回服务端收到的请求
+    fn received(method: &str, query: Option<Value>, payload: Value) -> Received {
+        let (base, server) = serve(1, |_| response("200 OK", &[], ""));
+        let spec =
+            RequestSpec::new(method, &format!("{}/hook", base), payload, 5).with_query(query);
+        assert_eq!(send_to(spec).unwrap().status, 200);
+        server.join().unwrap().remove(0)
+    }
+
+    #[test]
+    fn method_body_and_query_matrix() {
+        let q = || Some(json!({"q": "x"}));
+        let cases = [
+            // 未设置 query 的 GET: payload 作为查询参数, 不发送请求体
+            ("GET", None, json!({"a": 1}), "/hook?a=1", ""),
+            ("get", None, json!({"a": 1}), "/hook?a=1", ""),
+            ("GET", q(), json!({"a": 1}), "/hook?q=x", r#"{"a":1}"#),
+            ("GET", q(), json!({}), "/hook?q=x", ""),
+            ("POST", None, json!({"a": 1}), "/hook", r#"{"a":1}"#),
+            ("POST", q(), json!({"a": 1}), "/hook?q=x", r#"{"a":1}"#),
+            ("POST", None, json!({}), "/hook", "{}"),
+            ("PUT", None, json!({"a": 1}), "/hook", r#"{"a":1}"#),
+            ("PATCH", q(), json!({"a": 1}), "/hook?q=x", r#"{"a":1}"#),
+            ("DELETE", None, json!({"a": 1}), "/hook", r#"{"a":1}"#),
+            ("HEAD", None, json!({}), "/hook", ""),
+            ("OPTIONS", None, Value::Null, "/hook", ""),
+            ("OPTIONS", None, json!({"a": 1}), "/hook", r#"{"a":1}"#),
+            ("PURGE", q(), json!({"a": 1}), "/hook?q=x", r#"{"a":1}"#),
+        ];
+        for (method, query, payload, target, body) in cases {
+            let request = received(method, query, payload);
+            let case = format!("{} {}", method, target);
+            assert_eq!(request.method, method.to_uppercase(), "{}", case);
+            assert_eq!(request.target, target, "{}", case);
+            assert_eq!(request.text(), body, "{}", case);
+        }
+    }
+
+    #[test]
+    fn unsupported_method() {
+        let spec = RequestSpec::new("BAD METHOD", "http://127.0.0.1:1/", Value::Null, 5);
+        let err = send_to(spec).unwrap_err();
+        assert_eq!(err.kind(), "unsupported_method");
+    }
+
+    // 第一跳返回 status 与 Location: /next, 第二跳返回 200
+    fn redirected(status: &str, method: &str) -> Vec<Received> {
+        let status = status.to_string();
+        let (base, server) = serve(2, move |r| {
+            if r.target.starts_with("/next") {
+                response("200 OK", &[], "")
+            } else {
+                response(&status, &[("location", "/next")], "")
+            }
+        });
+        let spec = RequestSpec::new(method, &format!("{}/hook", base), json!({"a": 1}), 5)
+            .with_query(Some(json!({"q": "x"})));
+        let response = send_to(spec).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.redirects, 1);
+        assert!(response.url.ends_with("/next"));
+        server.join().unwrap()
+    }
+
+    #[test]
+    fn redirect_method_and_body() {
+        let cases = [
+            ("301 Moved Permanently", "POST", "GET", ""),
+            ("302 Found", "POST", "GET", ""),
+            ("303 See Other", "POST", "GET", ""),
+            ("303 See Other", "PUT", "GET", ""),
+            ("303 See Other", "HEAD", "HEAD", r#"{"a":1}"#),
+            ("301 Moved Permanently", "PUT", "PUT", r#"{"a":1}"#),
+            ("302 Found", "DELETE", "DELETE", r#"{"a":1}"#),
+            ("307 Temporary Redirect", "POST", "POST", r#"{"a":1}"#),
+            ("308 Permanent Redirect", "PATCH", "PATCH", r#"{"a":1}"#),
+        ];
+        for (status, method, next_method, next_body) in cases {
+            let requests = redirected(status, method);
+            let case = format!("{} {}", status, method);
+            assert_eq!(requests[0].target, "/hook?q=x", "{}", case);
+            // 查询参数只在第一跳发送
+            assert_eq!(requests[1].target, "/next", "{}", case);
+            assert_eq!(requests[1].method, next_method, "{}", case);
+            assert_eq!(requests[1].text(), next_body, "{}", case);
+        }
+    }
+
+    #[test]
+    fn cross_origin_redirect_drops_credentials() {
+        let (target, target_server) = serve(1, |_| response("200 OK", &[], ""));
+        let location = format!("{}/next", target);
+        let (base, server) = serve(1, move |_| {
+            response("307 Temporary Redirect", &[("location", &location)], "")
+        });
+        let headers = [
+            ("Cookie".to_string(), "a=1".to_string()),
+            ("X-Trace".to_string(), "t".to_string()),
+        ]
+        .into();
+        let spec = RequestSpec::new("POST", &format!("{}/hook", base), json!({}), 5)
+            .with_auth(
+                &headers,
+                Some(&Auth::Bearer {
+                    token: "secret".to_string(),
+                }),
+            )
+            .unwrap();
+        send_to(spec).unwrap();
+
+        let first = server.join().unwrap().remove(0);
+        assert_eq!(first.header("authorization"), Some("Bearer secret"));
+        assert_eq!(first.header("cookie"), Some("a=1"));
+        let second = target_server.join().unwrap().remove(0);
+        assert_eq!(second.header("authorization"), None);
+        assert_eq!(second.header("cookie"), None);
+        assert_eq!(second.header("x-trace"), Some("t"));
+    }
+
+    #[test]
+    fn redirect_limit() {
+        let (base, server) = serve(MAX_REDIRECTS as usize + 1, |_| {
+            response("302 Found", &[("location", "/loop")], "")
+        });
+        let spec = RequestSpec::new("GET", &format!("{}/loop", base), Value::Null, 5);
+        let err = send_to(spec).unwrap_err();
+        assert!(matches!(err, RequestError::TooManyRedirects(MAX_REDIRECTS)));
+        assert_eq!(err.kind(), "redirect_loop");
+        assert_eq!(server.join().unwrap().len(), MAX_REDIRECTS as usize + 1);
+    }
+
+    #[test]
+    fn invalid_location() {
+        let (base, server) = serve(1, |_| {
+            response("302 Found", &[("location", "http://[::1")], "")
+        });
+        let spec = RequestSpec::new("GET", &format!("{}/", base), Value::Null, 5);
+        let err = send_to(spec).unwrap_err();
+        assert!(matches!(err, RequestError::Redirect(_)), "{}", err);
+        assert_eq!(err.kind(), "redirect");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn classifies_network_errors() {
+        // .invalid 顶级域名保证无法解析
+        let spec = RequestSpec::new("GET", "http://task-io.invalid/", Value::Null, 5);
+        let err = send_to(spec).unwrap_err();
+        assert_eq!(err.kind(), "dns", "{}", err);
+        assert!(err.is_network());
+
+        // 对明文 HTTP 服务发起 TLS 握手
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("https://{}/", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.write_all(b"HTTP/1.1 400 Bad Request\r\nconnection: close\r\n\r\n");
+        });
+        let err = send_to(RequestSpec::new("GET", &url, Value::Null, 5)).unwrap_err();
+        assert_eq!(err.kind(), "tls", "{}", err);
+        assert!(matches!(err.error_class(), ErrorClass::HttpTlsError));
+        server.join().unwrap();
+
+        let (base, server) = serve(1, |_| {
+            std::thread::sleep(Duration::from_millis(1500));
+            response("200 OK", &[], "")
+        });
+        let err = send_to(RequestSpec::new("GET", &base, Value::Null, 1)).unwrap_err();
+        assert_eq!(err.kind(), "timeout", "{}", err);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn redact_url_hides_query_values_and_password() {
+        let url = reqwest::Url::parse("http://user:pw@example.com/hook?key=topsecret&a=1").unwrap();
+        let redacted = redact_url(&url).to_string();
+        assert_eq!(redacted, "http://user@example.com/hook?key=***&a=***");
+    }
+
+    #[test]
+    fn send_error_does_not_leak_query() {
+        let client = reqwest::blocking::Client::new();
+        let mut spec = RequestSpec::new("GET", "http://127.0.0.1:1/hook", Value::Null, 5);
+        spec.query = Some(serde_json::json!({"key": "topsecret123"}));
+        let err = send(&client, &spec).unwrap_err();
+        assert_eq!(err.kind(), "refused");
+        assert!(!err.to_string().contains("topsecret123"), "{}", err);
+    }
+}
@@ -1,121 +1,434 @@
 use glob::glob;
 use pyo3::prelude::*;
 use serde_json::Value;
+use std::collections::BTreeMap;
 use std::fs;
+use std::path::Path;
+
+mod aio;
+mod atomic;
+mod auth;
+mod body;
+mod client;
+mod config;
+mod daemon;
+mod due;
+mod errors;
+mod expect;
+mod history;
+mod http;
+mod limit;
+mod misfire;
+mod preserve;
+mod response;
+mod retry;
+mod runner;
+mod schedule;
+mod state;
+#[cfg(test)]
+mod testing;
+mod tz;
+mod validate;
+
+pub use atomic::{backup_path, write_atomic};
+pub use auth::{Auth, KeyLocation};
+pub use body::{BodyType, FilePart};
+pub use client::{ClientOptions, HttpClient};
+pub use config::{parse_duration, TaskConfig, TIME_FORMAT};
+pub use daemon::{Change, Daemon, DaemonEvent};
+pub use due::{evaluate, DueResult, DueState};
+pub use errors::ErrorClass;
+pub use expect::Expect;
+pub use history::{failure_counts, AttemptRecord, DEFAULT_HISTORY_PATH};
+pub use http::{RequestError, RequestSpec, NETWORK_ERROR_KINDS};
+pub use misfire::{MisfirePolicy, MAX_CATCHUP};
+pub use preserve::{render_update, JsonStyle};
+pub use response::Response;
+pub use retry::{RetryPolicy, StatusPattern};
+pub use runner::{
+    fire, inject_keys, load_config, load_secret_keys, run_once, FireOutcome, RunOptions,
+    TaskReport, ENV_KEY_NAME,
+};
+pub use schedule::{parse_cron, Schedule};
+pub use state::{StateStore, TaskState, DEFAULT_STATE_PATH};
+pub use tz::{localize, parse_timezone, DstPolicy, LocalizeError};
+pub use validate::{validate_directory, validate_file, Diagnostic, Severity};
+
+// 以下函数在读写文件、发送请求期间都会释放 GIL, 不会阻塞同时运行的其他 Python 线程
 
 // 1. 扫描目录获取 .json 文件列表 (保持不变)
 #[pyfunction]
-fn list_configs(dir: String) -> PyResult<Vec<String>> {
+fn list_configs(dir: String, py: Python) -> PyResult<Vec<String>> {
+    Ok(py.allow_threads(|| scan_configs(&dir)))
+}
+
+/// 返回目录下按文件名排序的 .json 文件路径
+pub fn scan_configs(dir: &str) -> Vec<String> {
     let pattern = format!("{}/*.json", dir);
     let mut files = Vec::new();
     if let Ok(paths) = glob(&pattern) {
-        for entry in paths {
-            if let Ok(path) = entry {
-                if let Some(path_str) = path.to_str() {
-                    files.push(path_str.to_string());
-                }
+        for path in paths.flatten() {
+            if let Some(path_str) = path.to_str() {
+                files.push(path_str.to_string());
             }
         }
     }
     files.sort();
-    Ok(files)
+    files
 }
 
 // 2. 读取 JSON (保持不变)
 #[pyfunction]
 fn read_config(path: String, py: Python) -> PyResult<PyObject> {
-    let content = fs::read_to_string(&path).map_err(|e| {
-        PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("读取失败 {}: {}", path, e))
-    })?;
-    let v: Value = serde_json::from_str(&content).map_err(|e| {
-        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("JSON 格式错误 {}: {}", path, e))
-    })?;
+    let v: Value = py
+        .allow_threads(|| fs::read_to_string(&path).map(|c| serde_json::from_str(&c)))
+        .map_err(|e| errors::read_error(py, &path, format!("读取失败 {}: {}", path, e), &e))?
+        .map_err(|e| {
+            errors::parse_error(
+                py,
+                &path,
+                format!("JSON 格式错误 {}: {}", path, e),
+                &e,
+                None,
+            )
+        })?;
     pythonize::pythonize(py, &v)
         .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
 }
 
-// 3. 保存 JSON (保持不变)
+// 3. 保存 JSON (只改写变化的字段并沿用原文件排版; 原子写入, backup=True 时保留 .bak)
 #[pyfunction]
-fn save_config(path: String, data: PyObject, py: Python) -> PyResult<()> {
+#[pyo3(signature = (path, data, backup=false))]
+fn save_config(path: String, data: PyObject, backup: bool, py: Python) -> PyResult<()> {
     let v: Value = pythonize::depythonize(data.as_ref(py)).map_err(|e| {
         PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
             "无法转换 Python 对象为 JSON: {}",
             e
         ))
     })?;
-    let content = serde_json::to_string_pretty(&v).map_err(|e| {
+    let existing = py.allow_threads(|| fs::read_to_string(&path).ok());
+    let content = render_update(existing.as_deref(), None, &v).map_err(|e| {
         PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("JSON 序列化失败: {}", e))
     })?;
-    fs::write(&path, content).map_err(|e| {
-        PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("写入失败 {}: {}", path, e))
-    })?;
-    Ok(())
+    py.allow_threads(|| write_atomic(Path::new(&path), content.as_bytes(), backup))
+        .map_err(|e| errors::write_error(py, &path, format!("写入失败 {}: {}", path, e), &e))
 }
 
 // 4. 新增: 发送 HTTP 请求
-// 参数: method (GET/POST), url, payload (字典), timeout (秒)
-// 返回: (status_code, response_text) 的元组
+// 参数: method (GET/POST/PUT/DELETE/PATCH/HEAD/OPTIONS 或自定义方法), url, payload (字典), timeout (秒)
+// 未提供 query 时 GET 请求把 payload 作为查询参数; 提供 query 时 payload 总是作为请求体
+// 可选: journal 为执行历史文件, 提供时每次调用都会追加一条记录 (task 与 attempt 一并写入)
+// 可选: headers 为请求头字典, auth 为认证字典 (同配置中的 auth), 省略时使用 task 中的配置
+//       其中的 ${NAME} 替换为环境变量, 变量未设置时抛出 ValueError
+// 可选: body_type 为 json/form/text/raw/multipart, query 为查询参数字典, 省略时使用 task 中的配置
+// 可选: client 为 HttpClient, 省略时使用进程内共享的默认 Client (同样复用连接)
+// 返回: Response (状态码、响应头、最终 URL、耗时、响应体等), 仍可按 status, text = ... 解包
 #[pyfunction]
+#[pyo3(signature = (method, url, payload, timeout_secs, journal=None, task=None, attempt=1, headers=None, auth=None, body_type=None, query=None, client=None))]
+#[allow(clippy::too_many_arguments)]
 fn send_request(
     method: String,
     url: String,
     payload: PyObject,
     timeout_secs: u64,
+    journal: Option<String>,
+    task: Option<PyRef<TaskConfig>>,
+    attempt: u32,
+    headers: Option<BTreeMap<String, String>>,
+    auth: Option<PyObject>,
+    body_type: Option<String>,
+    query: Option<PyObject>,
+    client: Option<PyRef<HttpClient>>,
     py: Python,
-) -> PyResult<(u16, String)> {
-    // 1. 将 Python Payload 转为 Rust JSON Value
-    let json_payload: Value = pythonize::depythonize(payload.as_ref(py)).map_err(|e| {
-        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Payload 转换失败: {}", e))
-    })?;
+) -> PyResult<Response> {
+    // 1. 将 Python 参数转为请求
+    let spec = request_spec(
+        &method,
+        &url,
+        payload,
+        timeout_secs,
+        headers,
+        auth,
+        body_type,
+        query,
+        task.as_deref(),
+        py,
+    )?;
 
-    // 2. 构建 Client
-    let client = reqwest::blocking::Client::builder()
-        .timeout(std::time::Duration::from_secs(timeout_secs))
-        .build()
-        .map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("构建 Client 失败: {}", e))
-        })?;
+    // 2. 释放 GIL, 发送请求并写入执行历史
+    let client = client.map_or_else(HttpClient::shared, |c| c.clone());
+    let task = task.map(|t| (t.task_id(), t.task_name.clone()));
+    Ok(py.allow_threads(|| send_journaled(&client, &spec, attempt, journal.as_deref(), task))?)
+}
 
-    // 3. 构建请求
-    let method_upper = method.to_uppercase();
-    let request_builder = match method_upper.as_str() {
-        "GET" => {
-            // 对于 GET 请求，通常将 payload 作为 Query Params
-            // 这里我们需要将 json_payload (Value) 转换成 Map 才能传给 .query()
-            // 如果结构太复杂，简单处理可以直接传 json，视 API 要求而定
-            // 这里为了通用性，如果方法是 GET，且 payload 是对象，则尝试转为 query
-            client.get(&url).query(&json_payload)
-        }
-        "POST" => client.post(&url).json(&json_payload),
-        "PUT" => client.put(&url).json(&json_payload),
-        "DELETE" => client.delete(&url).json(&json_payload),
-        _ => {
-            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
-                "不支持的方法: {}",
-                method
-            )))
+// 4.1 send_request 的异步版本, 参数与返回值相同, 需在 asyncio 事件循环中 await
+// 请求在 Tokio 的阻塞线程池中发送 (不持有 GIL), 多个请求可以用 asyncio.gather 并发
+#[pyfunction]
+#[pyo3(signature = (method, url, payload, timeout_secs, journal=None, task=None, attempt=1, headers=None, auth=None, body_type=None, query=None, client=None))]
+#[allow(clippy::too_many_arguments)]
+fn send_request_async<'py>(
+    method: String,
+    url: String,
+    payload: PyObject,
+    timeout_secs: u64,
+    journal: Option<String>,
+    task: Option<PyRef<TaskConfig>>,
+    attempt: u32,
+    headers: Option<BTreeMap<String, String>>,
+    auth: Option<PyObject>,
+    body_type: Option<String>,
+    query: Option<PyObject>,
+    client: Option<PyRef<HttpClient>>,
+    py: Python<'py>,
+) -> PyResult<&'py PyAny> {
+    // Python 对象只能在持有 GIL 时读取, 先转换好再交给 Tokio
+    let spec = request_spec(
+        &method,
+        &url,
+        payload,
+        timeout_secs,
+        headers,
+        auth,
+        body_type,
+        query,
+        task.as_deref(),
+        py,
+    )?;
+    let client = client.map_or_else(HttpClient::shared, |c| c.clone());
+    let task = task.map(|t| (t.task_id(), t.task_name.clone()));
+    aio::spawn_awaitable(py, move || {
+        send_journaled(&client, &spec, attempt, journal.as_deref(), task)
+    })
+}
+
+// send_request 与 send_request_async 共用: 发送一次并追加执行历史 (若提供 journal)
+// task 为 (task_id, task_name); 写入失败时原因记在 Response.journal_error 中
+fn send_journaled(
+    client: &HttpClient,
+    spec: &RequestSpec,
+    attempt: u32,
+    journal: Option<&str>,
+    task: Option<(String, Option<String>)>,
+) -> Result<Response, RequestError> {
+    let (mut record, result) = runner::send_recorded(client, spec, attempt);
+    if let Some(journal) = journal {
+        if let Some((task_id, task_name)) = task {
+            record.task_id = Some(task_id);
+            record.task_name = task_name;
         }
+        history::journal(Path::new(journal), &mut record);
+    }
+    result.map(|response| Response {
+        journal_error: record.journal_error,
+        ..response
+    })
+}
+
+// 5. 按重试策略发送 (指数退避 + 抖动, 遵循 Retry-After)
+// retry 为策略字典, 省略时使用 task.retry, 二者都没有时使用默认策略 (3 次, 重试 408/429/5xx)
+// 每次尝试都会追加到 journal (若提供); 返回全部尝试记录, 最后一条的 ok 表示是否成功
+// expect 为成功断言字典 (同配置中的 expect), 省略时使用 task.expect, 都没有时 2xx 即成功
+// headers / auth / body_type / query / client 同 send_request
+#[pyfunction]
+#[pyo3(signature = (method, url, payload, timeout_secs, retry=None, journal=None, task=None, expect=None, headers=None, auth=None, body_type=None, query=None, client=None))]
+#[allow(clippy::too_many_arguments)]
+fn send_with_retry(
+    method: String,
+    url: String,
+    payload: PyObject,
+    timeout_secs: u64,
+    retry: Option<PyObject>,
+    journal: Option<String>,
+    task: Option<PyRef<TaskConfig>>,
+    expect: Option<PyObject>,
+    headers: Option<BTreeMap<String, String>>,
+    auth: Option<PyObject>,
+    body_type: Option<String>,
+    query: Option<PyObject>,
+    client: Option<PyRef<HttpClient>>,
+    py: Python,
+) -> PyResult<Vec<AttemptRecord>> {
+    let mut spec = request_spec(
+        &method,
+        &url,
+        payload,
+        timeout_secs,
+        headers,
+        auth,
+        body_type,
+        query,
+        task.as_deref(),
+        py,
+    )?;
+    if let Some(expect) = expect {
+        let expect: Expect = pythonize::depythonize(expect.as_ref(py)).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("expect 格式错误: {}", e))
+        })?;
+        expect
+            .check_config()
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+        spec.expect = Some(expect);
+    }
+    let policy: RetryPolicy = match (retry, &task) {
+        (Some(retry), _) => pythonize::depythonize(retry.as_ref(py)).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("retry 格式错误: {}", e))
+        })?,
+        (None, Some(task)) => task.retry.clone().unwrap_or_default(),
+        (None, None) => RetryPolicy::default(),
     };
+    policy
+        .check()
+        .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
 
-    // 4. 发送请求
-    let response = request_builder.send().map_err(|e| {
-        // 将 reqwest 错误转换为 Python 异常
-        PyErr::new::<pyo3::exceptions::PyConnectionError, _>(format!("网络请求失败: {}", e))
-    })?;
+    let task_id = task.as_ref().map(|t| t.task_id());
+    let task_name = task.as_ref().and_then(|t| t.task_name.clone());
+    let client = client.map_or_else(HttpClient::shared, |c| c.clone());
+    // 重试之间的等待同样不持有 GIL
+    let (attempts, _) = py.allow_threads(|| {
+        runner::send_with_retry(&client, &spec, &policy, |record| {
+            record.task_id = task_id.clone();
+            record.task_name = task_name.clone();
+            if let Some(journal) = &journal {
+                history::journal(Path::new(journal), record);
+            }
+        })
+    });
+    Ok(attempts)
+}
 
-    // 5. 获取结果
-    let status = response.status().as_u16();
-    let text = response.text().unwrap_or_default();
+// 6. 执行一轮调度: 判断 dir 下的任务是否到期, 最多 concurrency 个任务同时发送
+// 执行状态写入 state_path, 每次尝试追加到 journal (为 None 时不写), DEVICE_KEYS 中的 Key 注入 body
+// host_rate_limit 为每个主机每秒最多发起的请求数 (含重试), 也可以在传入的 client 中设置
+// 运行期间释放 GIL; 返回按文件名排序的 TaskReport (到期状态、是否发送、是否成功、每次尝试的记录)
+#[pyfunction]
+#[pyo3(signature = (dir, concurrency=4, state_path=DEFAULT_STATE_PATH.to_string(), journal=Some(DEFAULT_HISTORY_PATH.to_string()), tolerance_minutes=30, timeout_secs=20, host_rate_limit=None, client=None))]
+#[allow(clippy::too_many_arguments)]
+fn run_due_tasks(
+    dir: String,
+    concurrency: usize,
+    state_path: String,
+    journal: Option<String>,
+    tolerance_minutes: i64,
+    timeout_secs: u64,
+    host_rate_limit: Option<f64>,
+    client: Option<PyRef<HttpClient>>,
+    py: Python,
+) -> PyResult<Vec<TaskReport>> {
+    let client = match (client, host_rate_limit) {
+        (Some(_), Some(_)) => {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "指定 client 时请在 HttpClient 中设置 host_rate_limit",
+            ))
+        }
+        (Some(client), None) => client.clone(),
+        (None, Some(_)) => HttpClient::new(ClientOptions {
+            host_rate_limit,
+            ..ClientOptions::default()
+        })?,
+        (None, None) => HttpClient::shared(),
+    };
+    let opts = RunOptions {
+        config_dir: dir,
+        state_path: state_path.into(),
+        history_path: journal.map(Into::into),
+        tolerance: chrono::Duration::minutes(tolerance_minutes),
+        timeout_secs,
+        secret_keys: load_secret_keys(ENV_KEY_NAME)
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?,
+        client,
+        concurrency,
+        ..RunOptions::default()
+    };
+    py.allow_threads(|| run_once(&opts, chrono::Utc::now(), |_| {}))
+        .map_err(|e| {
+            let path = opts.state_path.display().to_string();
+            errors::new_error(
+                py,
+                ErrorClass::ConfigReadError,
+                e,
+                &[("path", path.into_py(py))],
+            )
+        })
+}
 
-    Ok((status, text))
+// 由 Python 参数构建请求; headers / auth / body_type / query 省略时取 task 中的配置
+#[allow(clippy::too_many_arguments)]
+fn request_spec(
+    method: &str,
+    url: &str,
+    payload: PyObject,
+    timeout_secs: u64,
+    headers: Option<BTreeMap<String, String>>,
+    auth: Option<PyObject>,
+    body_type: Option<String>,
+    query: Option<PyObject>,
+    task: Option<&TaskConfig>,
+    py: Python,
+) -> PyResult<RequestSpec> {
+    let json_payload: Value = pythonize::depythonize(payload.as_ref(py)).map_err(|e| {
+        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Payload 转换失败: {}", e))
+    })?;
+    let auth: Option<Auth> = match (auth, task) {
+        (Some(auth), _) => Some(pythonize::depythonize(auth.as_ref(py)).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("auth 格式错误: {}", e))
+        })?),
+        (None, Some(task)) => task.auth.clone(),
+        (None, None) => None,
+    };
+    let body_type = match (body_type, task) {
+        (Some(t), _) => {
+            BodyType::parse(&t).map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?
+        }
+        (None, Some(task)) => task.body_type,
+        (None, None) => BodyType::default(),
+    };
+    let query: Option<Value> = match (query, task) {
+        (Some(query), _) => Some(pythonize::depythonize(query.as_ref(py)).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("query 格式错误: {}", e))
+        })?),
+        (None, Some(task)) => task.query.clone().map(Value::Object),
+        (None, None) => None,
+    };
+    let headers = headers
+        .or_else(|| task.map(|t| t.headers.clone()))
+        .unwrap_or_default();
+    Ok(RequestSpec::new(method, url, json_payload, timeout_secs)
+        .with_body_type(body_type)
+        .with_query(query)
+        .with_expect(task.and_then(|t| t.expect.clone()))
+        .with_auth(&headers, auth.as_ref())?)
 }
 
 #[pymodule]
-fn task_io(_py: Python, m: &PyModule) -> PyResult<()> {
+fn task_io(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(list_configs, m)?)?;
     m.add_function(wrap_pyfunction!(read_config, m)?)?;
     m.add_function(wrap_pyfunction!(save_config, m)?)?;
     // 注册新函数
     m.add_function(wrap_pyfunction!(send_request, m)?)?;
+    m.add_function(wrap_pyfunction!(send_request_async, m)?)?;
+    m.add_function(wrap_pyfunction!(send_with_retry, m)?)?;
+    m.add_function(wrap_pyfunction!(run_due_tasks, m)?)?;
+    m.add_class::<TaskReport>()?;
+    errors::register(py, m)?;
+    m.add_class::<Response>()?;
+    m.add_class::<HttpClient>()?;
+    m.add_class::<TaskConfig>()?;
+    m.add_function(wrap_pyfunction!(validate::validate_config, m)?)?;
+    m.add_function(wrap_pyfunction!(validate::validate_dir, m)?)?;
+    m.add_class::<Diagnostic>()?;
+    m.add_function(wrap_pyfunction!(due::evaluate_due, m)?)?;
+    m.add_class::<DueState>()?;
+    m.add_class::<DueResult>()?;
+    m.add_function(wrap_pyfunction!(tz::localize_time, m)?)?;
+    m.add_function(wrap_pyfunction!(state::is_executed, m)?)?;
+    m.add_function(wrap_pyfunction!(state::mark_executed, m)?)?;
+    m.add_function(wrap_pyfunction!(state::reset, m)?)?;
+    m.add_function(wrap_pyfunction!(state::record_skipped, m)?)?;
+    m.add("DEFAULT_STATE_PATH", DEFAULT_STATE_PATH)?;
+    m.add_function(wrap_pyfunction!(history::history, m)?)?;
+    m.add_function(wrap_pyfunction!(history::failure_summary, m)?)?;
+    m.add_class::<AttemptRecord>()?;
+    m.add("DEFAULT_HISTORY_PATH", DEFAULT_HISTORY_PATH)?;
     Ok(())
 }
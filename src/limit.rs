@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 按主机限制请求速率: 发往同一主机的两个请求之间至少间隔 `interval`
+///
+/// 并发发送时各线程按顺序预约时间片, 多个任务同时打到同一个 Webhook 时会被均匀错开。
+#[derive(Debug)]
+pub struct HostLimiter {
+    interval: Duration,
+    next: Mutex<HashMap<String, Instant>>,
+}
+
+impl HostLimiter {
+    /// `per_second` 为每个主机每秒最多发起的请求数
+    pub fn new(per_second: f64) -> Result<Self, String> {
+        if !(per_second.is_finite() && per_second > 0.0) {
+            return Err(format!("每秒请求数必须为正数: {}", per_second));
+        }
+        let interval = Duration::try_from_secs_f64(1.0 / per_second)
+            .ok()
+            .filter(|d| Instant::now().checked_add(*d).is_some())
+            .ok_or_else(|| format!("每秒请求数过小: {}", per_second))?;
+        Ok(HostLimiter {
+            interval,
+            next: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// 等到 `url` 所在主机的下一个可用时间片; 无法解析主机时不等待
+    pub fn wait(&self, url: &str) {
+        let Some(host) = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+        else {
+            return;
+        };
+        let now = Instant::now();
+        let slot = {
+            let mut next = self.next.lock().unwrap();
+            let slot = next.get(&host).map_or(now, |&t| t.max(now));
+            // 间隔极大时时间片可能超出 Instant 的范围, 此时保持原时间片
+            next.insert(host, slot.checked_add(self.interval).unwrap_or(slot));
+            slot
+        };
+        if slot > now {
+            std::thread::sleep(slot - now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_rejects_invalid_rates() {
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY, 1e-30] {
+            assert!(HostLimiter::new(rate).is_err(), "{}", rate);
+        }
+        let limiter = HostLimiter::new(4.0).unwrap();
+        assert_eq!(limiter.interval, Duration::from_millis(250));
+    }
+}
@@ -0,0 +1,87 @@
+use crate::config::duration_format;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// 补发最多回溯多久, 避免长时间停机后补发过量请求
+pub const MAX_CATCHUP: Duration = Duration::days(7);
+
+/// 错过触发时间 (超过容忍窗口) 后如何处理
+///
+/// ```json
+/// "misfire_policy": "fire_once"
+/// "misfire_policy": { "fire_if_within": "6h" }
+/// ```
+///
+/// - skip: 超过容忍窗口即放弃 (默认, 与原先行为一致)
+/// - fire_once: 无论晚多久都补发; 周期任务错过多次时只补发最近一次
+/// - fire_all: 无论晚多久都补发; 周期任务错过的每个触发点依次补发
+/// - fire_if_within: 用给定时长代替全局容忍窗口
+///
+/// 补发最多回溯 MAX_CATCHUP; 放弃的触发点会记录到执行状态中。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MisfirePolicy {
+    #[default]
+    Skip,
+    FireOnce,
+    FireAll,
+    FireIfWithin(#[serde(with = "duration_format")] Duration),
+}
+
+impl MisfirePolicy {
+    /// 触发点在多久以内仍会执行; 补发策略返回 MAX_CATCHUP
+    pub fn window(&self, tolerance: Duration) -> Duration {
+        match self {
+            MisfirePolicy::Skip => tolerance,
+            MisfirePolicy::FireIfWithin(d) => *d,
+            MisfirePolicy::FireOnce | MisfirePolicy::FireAll => MAX_CATCHUP,
+        }
+    }
+}
+
+impl fmt::Display for MisfirePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MisfirePolicy::Skip => write!(f, "skip"),
+            MisfirePolicy::FireOnce => write!(f, "fire_once"),
+            MisfirePolicy::FireAll => write!(f, "fire_all"),
+            MisfirePolicy::FireIfWithin(d) => {
+                write!(f, "fire_if_within: {}", duration_format::format(*d))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_policies() {
+        let parse = |v| serde_json::from_value::<MisfirePolicy>(v);
+        assert_eq!(parse(json!("fire_once")).unwrap(), MisfirePolicy::FireOnce);
+        assert_eq!(
+            parse(json!({"fire_if_within": "6h"})).unwrap(),
+            MisfirePolicy::FireIfWithin(Duration::hours(6))
+        );
+        assert!(parse(json!("fire_twice")).is_err());
+        assert!(parse(json!({"fire_if_within": "999999999999999d"})).is_err());
+    }
+
+    #[test]
+    fn windows() {
+        let tolerance = Duration::minutes(30);
+        assert_eq!(MisfirePolicy::Skip.window(tolerance), tolerance);
+        assert_eq!(MisfirePolicy::FireAll.window(tolerance), MAX_CATCHUP);
+        assert_eq!(
+            MisfirePolicy::FireIfWithin(Duration::hours(6)).window(tolerance),
+            Duration::hours(6)
+        );
+        assert_eq!(
+            MisfirePolicy::FireIfWithin(Duration::hours(6)).to_string(),
+            "fire_if_within: 6h"
+        );
+    }
+}
@@ -0,0 +1,562 @@
+use serde::Serialize;
+use serde_json::ser::PrettyFormatter;
+use serde_json::{Map, Value};
+
+/// 原文件的排版风格: 缩进字符串、换行符、末尾是否有换行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonStyle {
+    pub indent: String,
+    pub crlf: bool,
+    pub trailing_newline: bool,
+}
+
+impl Default for JsonStyle {
+    // 与 serde_json::to_string_pretty 的输出一致
+    fn default() -> Self {
+        JsonStyle {
+            indent: "  ".to_string(),
+            crlf: false,
+            trailing_newline: false,
+        }
+    }
+}
+
+impl JsonStyle {
+    /// 从已有文件内容推断排版风格, 取第一行缩进作为缩进单位
+    pub fn detect(content: &str) -> Self {
+        let mut style = JsonStyle {
+            crlf: content.contains("\r\n"),
+            trailing_newline: content.ends_with('\n'),
+            ..JsonStyle::default()
+        };
+        let indent = content
+            .lines()
+            .skip(1)
+            .map(|line| {
+                let trimmed = line.trim_start_matches([' ', '\t']);
+                &line[..line.len() - trimmed.len()]
+            })
+            .find(|ws| !ws.is_empty());
+        if let Some(ws) = indent {
+            style.indent = ws.to_string();
+        }
+        style
+    }
+
+    pub fn render(&self, value: &Value) -> serde_json::Result<String> {
+        let mut buf = Vec::new();
+        let formatter = PrettyFormatter::with_indent(self.indent.as_bytes());
+        let mut ser = serde_json::Serializer::with_formatter(&mut buf, formatter);
+        value.serialize(&mut ser)?;
+        // serde_json 只输出合法 UTF-8
+        let mut out = String::from_utf8(buf).unwrap_or_default();
+        if self.trailing_newline {
+            out.push('\n');
+        }
+        if self.crlf {
+            out = out.replace('\n', "\r\n");
+        }
+        Ok(out)
+    }
+}
+
+/// 只把 `before` -> `after` 之间发生变化的字段应用到 `original` 上
+///
+/// - `before` 与 `after` 相同的字段保留 `original` 中的原样 (包括是否存在)
+/// - 变化的字段原位替换, 新增字段追加在末尾, 删除的字段移除
+/// - 两边都是对象时递归处理, 以保持嵌套对象 (如 body) 的键顺序
+pub fn patch(original: &Value, before: &Value, after: &Value) -> Value {
+    let (Value::Object(orig), Value::Object(after_map)) = (original, after) else {
+        return after.clone();
+    };
+    let empty = Map::new();
+    let before_map = before.as_object().unwrap_or(&empty);
+
+    let mut out = Map::new();
+    for (key, value) in orig {
+        let old = before_map.get(key);
+        match after_map.get(key) {
+            new if new == old => {
+                out.insert(key.clone(), value.clone());
+            }
+            Some(new) => {
+                let old = old.unwrap_or(&Value::Null);
+                out.insert(key.clone(), patch(value, old, new));
+            }
+            None => {}
+        }
+    }
+    for (key, value) in after_map {
+        if !orig.contains_key(key) && before_map.get(key) != Some(value) {
+            out.insert(key.clone(), value.clone());
+        }
+    }
+    Value::Object(out)
+}
+
+/// 生成写回文件的文本: 有原文件时只修改变化的字段并沿用原排版, 否则使用默认排版
+///
+/// `before` 为 None 时以原文件内容作为修改前的状态。
+/// 变化的值直接写入原文本 (见 splice), 未变化的部分逐字保留。
+pub fn render_update(
+    existing: Option<&str>,
+    before: Option<Value>,
+    after: &Value,
+) -> serde_json::Result<String> {
+    let original = existing.and_then(|c| Some((c, serde_json::from_str::<Value>(c).ok()?)));
+    match original {
+        Some((content, original)) => {
+            let before = before.unwrap_or_else(|| original.clone());
+            let style = JsonStyle::detect(content);
+            match splice(content, &style, &before, after) {
+                Some(text) => Ok(text),
+                None => style.render(&patch(&original, &before, after)),
+            }
+        }
+        None => JsonStyle::default().render(after),
+    }
+}
+
+/// 与 patch 规则相同, 但直接修改原文本: 只改写变化的值, 其余字符 (包括紧凑写法的对象、数组) 原样保留
+///
+/// 原文本顶层不是对象或含有重复的键时返回 None。
+pub fn splice(content: &str, style: &JsonStyle, before: &Value, after: &Value) -> Option<String> {
+    let root = Scanner {
+        text: content.as_bytes(),
+        pos: 0,
+    }
+    .parse()?;
+    root.members.as_ref()?;
+    let object = Splicer {
+        text: content,
+        style,
+    }
+    .object(&root, before, after)?;
+    Some(format!(
+        "{}{}{}",
+        &content[..root.start],
+        object,
+        &content[root.end..]
+    ))
+}
+
+// 原文本中一个值的位置; 对象另外记录各成员的位置
+struct Node {
+    start: usize,
+    end: usize,
+    members: Option<Vec<Member>>,
+}
+
+struct Member {
+    key: String,
+    // 键的起始位置
+    start: usize,
+    value: Node,
+}
+
+// 只记录位置的 JSON 扫描器, 输入已由 serde_json 校验过
+struct Scanner<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl Scanner<'_> {
+    fn parse(mut self) -> Option<Node> {
+        self.skip_ws();
+        self.value()
+    }
+
+    fn skip_ws(&mut self) {
+        while self
+            .text
+            .get(self.pos)
+            .is_some_and(|b| b.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_ws();
+        if self.text.get(self.pos) == Some(&byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self) -> Option<Node> {
+        let start = self.pos;
+        let mut members = None;
+        match *self.text.get(start)? {
+            b'{' => members = Some(self.object()?),
+            b'[' => self.array()?,
+            b'"' => self.string()?,
+            _ => {
+                while self
+                    .text
+                    .get(self.pos)
+                    .is_some_and(|b| !b",]} \t\r\n".contains(b))
+                {
+                    self.pos += 1;
+                }
+            }
+        }
+        Some(Node {
+            start,
+            end: self.pos,
+            members,
+        })
+    }
+
+    fn string(&mut self) -> Option<()> {
+        self.pos += 1;
+        loop {
+            match *self.text.get(self.pos)? {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return Some(());
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn object(&mut self) -> Option<Vec<Member>> {
+        self.pos += 1;
+        let mut members: Vec<Member> = Vec::new();
+        if self.eat(b'}') {
+            return Some(members);
+        }
+        loop {
+            self.skip_ws();
+            let start = self.pos;
+            self.string()?;
+            let key: String = serde_json::from_slice(&self.text[start..self.pos]).ok()?;
+            if members.iter().any(|m| m.key == key) {
+                return None;
+            }
+            if !self.eat(b':') {
+                return None;
+            }
+            self.skip_ws();
+            let value = self.value()?;
+            members.push(Member { key, start, value });
+            if self.eat(b'}') {
+                return Some(members);
+            }
+            if !self.eat(b',') {
+                return None;
+            }
+        }
+    }
+
+    fn array(&mut self) -> Option<()> {
+        self.pos += 1;
+        if self.eat(b']') {
+            return Some(());
+        }
+        loop {
+            self.skip_ws();
+            self.value()?;
+            if self.eat(b']') {
+                return Some(());
+            }
+            if !self.eat(b',') {
+                return None;
+            }
+        }
+    }
+}
+
+struct Splicer<'a> {
+    text: &'a str,
+    style: &'a JsonStyle,
+}
+
+impl Splicer<'_> {
+    // 对象 node 修改后的文本, 规则同 patch
+    fn object(&self, node: &Node, before: &Value, after: &Value) -> Option<String> {
+        let members = node.members.as_ref()?;
+        let Value::Object(after_map) = after else {
+            return self.render(after, node.start);
+        };
+        let empty = Map::new();
+        let before_map = before.as_object().unwrap_or(&empty);
+        let text = self.text;
+        // 原来写在一行内的非空对象, 新值也写成一行
+        let inline = !members.is_empty() && !text[node.start..node.end].contains('\n');
+
+        // (成员在原对象中的下标, 成员文本); 新增成员的下标为 None
+        let mut pieces: Vec<(Option<usize>, String)> = Vec::new();
+        let mut changed = false;
+        for (i, member) in members.iter().enumerate() {
+            let old = before_map.get(&member.key);
+            let value = match after_map.get(&member.key) {
+                new if new == old => text[member.value.start..member.value.end].to_string(),
+                Some(new) => {
+                    changed = true;
+                    let old = old.unwrap_or(&Value::Null);
+                    match (&member.value.members, new) {
+                        (Some(_), Value::Object(_)) => self.object(&member.value, old, new)?,
+                        _ if inline => serde_json::to_string(new).ok()?,
+                        _ => self.render(new, member.start)?,
+                    }
+                }
+                None => {
+                    changed = true;
+                    continue;
+                }
+            };
+            pieces.push((
+                Some(i),
+                format!("{}{}", &text[member.start..member.value.start], value),
+            ));
+        }
+        for (key, value) in after_map {
+            if members.iter().all(|m| &m.key != key) && before_map.get(key) != Some(value) {
+                changed = true;
+                let line = self.child_indent(node);
+                let key = serde_json::to_string(key).ok()?;
+                let value = if inline {
+                    serde_json::to_string(value).ok()?
+                } else {
+                    self.render_at(value, &line)?
+                };
+                pieces.push((None, format!("{}: {}", key, value)));
+            }
+        }
+        if !changed {
+            return Some(text[node.start..node.end].to_string());
+        }
+
+        let (Some(first), Some(last)) = (members.first(), members.last()) else {
+            // 原来是空对象: 按默认排版展开
+            if pieces.is_empty() {
+                return Some(text[node.start..node.end].to_string());
+            }
+            let nl = self.newline();
+            let inner = self.child_indent(node);
+            let body: Vec<String> = pieces.into_iter().map(|(_, p)| p).collect();
+            return Some(format!(
+                "{{{nl}{inner}{}{nl}{}}}",
+                body.join(&format!(",{nl}{inner}")),
+                self.line_indent(node.start)
+            ));
+        };
+        if pieces.is_empty() {
+            return Some("{}".to_string());
+        }
+        let default_sep = match members.get(1) {
+            Some(second) => text[first.value.end..second.start].to_string(),
+            None => {
+                let lead = &text[node.start + 1..first.start];
+                if lead.contains('\n') {
+                    format!(",{}", lead)
+                } else {
+                    ", ".to_string()
+                }
+            }
+        };
+        let mut out = text[node.start..first.start].to_string();
+        for (n, (index, piece)) in pieces.iter().enumerate() {
+            if n > 0 {
+                // 沿用原文本中该成员前的分隔符 (逗号与换行缩进)
+                match index {
+                    Some(i) if *i > 0 => {
+                        out.push_str(&text[members[i - 1].value.end..members[*i].start])
+                    }
+                    _ => out.push_str(&default_sep),
+                }
+            }
+            out.push_str(piece);
+        }
+        out.push_str(&text[last.value.end..node.end]);
+        Some(out)
+    }
+
+    // 在 at 所在行的缩进下排版新值
+    fn render(&self, value: &Value, at: usize) -> Option<String> {
+        self.render_at(value, &self.line_indent(at))
+    }
+
+    fn render_at(&self, value: &Value, indent: &str) -> Option<String> {
+        let style = JsonStyle {
+            trailing_newline: false,
+            ..self.style.clone()
+        };
+        let rendered = style.render(value).ok()?;
+        let nl = self.newline();
+        Some(rendered.replace(nl, &format!("{}{}", nl, indent)))
+    }
+
+    fn newline(&self) -> &'static str {
+        if self.style.crlf {
+            "\r\n"
+        } else {
+            "\n"
+        }
+    }
+
+    // pos 所在行的前导空白
+    fn line_indent(&self, pos: usize) -> String {
+        let line_start = self.text[..pos].rfind('\n').map_or(0, |i| i + 1);
+        self.text[line_start..]
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect()
+    }
+
+    // 对象成员的缩进: 有成员时取第一个成员所在行, 否则比对象所在行多一级
+    fn child_indent(&self, node: &Node) -> String {
+        match node.members.as_ref().and_then(|m| m.first()) {
+            Some(first) => self.line_indent(first.start),
+            None => format!("{}{}", self.line_indent(node.start), self.style.indent),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // 以原文件为修改前状态, 对其解析结果做 edit 后写回
+    fn update(content: &str, edit: impl FnOnce(&mut Value)) -> String {
+        let before: Value = serde_json::from_str(content).unwrap();
+        let mut after = before.clone();
+        edit(&mut after);
+        let text = render_update(Some(content), None, &after).unwrap();
+        let patched = patch(&before, &before, &after);
+        assert_eq!(serde_json::from_str::<Value>(&text).unwrap(), patched);
+        text
+    }
+
+    #[test]
+    fn patch_applies_only_changes() {
+        // original 中的键顺序与 before 不同, 且多出 before 中没有的 extra
+        let original = json!({"b": 1, "a": {"y": 1, "x": 2}, "extra": true, "gone": 0});
+        let before = json!({"a": {"x": 2, "y": 1}, "b": 1, "gone": 0});
+        let after = json!({"a": {"x": 3, "y": 1}, "b": 1, "new": "n"});
+        let patched = patch(&original, &before, &after);
+        assert_eq!(
+            serde_json::to_string(&patched).unwrap(),
+            r#"{"b":1,"a":{"y":1,"x":3},"extra":true,"new":"n"}"#
+        );
+    }
+
+    #[test]
+    fn patch_replaces_non_objects() {
+        assert_eq!(patch(&json!([1]), &json!([1]), &json!([2])), json!([2]));
+        assert_eq!(
+            patch(
+                &json!({"a": [1, 2]}),
+                &json!({"a": [1, 2]}),
+                &json!({"a": [2]})
+            ),
+            json!({"a": [2]})
+        );
+        // 与修改前相同的字段不会被加回原文件
+        assert_eq!(
+            patch(&json!({}), &json!({"d": 1}), &json!({"d": 1})),
+            json!({})
+        );
+    }
+
+    #[test]
+    fn detect_style() {
+        let style = JsonStyle::detect("{\r\n\t\"a\": 1\r\n}\r\n");
+        assert_eq!(
+            style,
+            JsonStyle {
+                indent: "\t".to_string(),
+                crlf: true,
+                trailing_newline: true,
+            }
+        );
+        assert_eq!(
+            style.render(&json!({"a": [1]})).unwrap(),
+            "{\r\n\t\"a\": [\r\n\t\t1\r\n\t]\r\n}\r\n"
+        );
+        assert_eq!(JsonStyle::detect("{}"), JsonStyle::default());
+    }
+
+    #[test]
+    fn splice_keeps_compact_values() {
+        let content = "{\n  \"a\": {\"y\": 2, \"b\": [1, 2]},\n  \"executed\": false\n}\n";
+        let text = update(content, |v| v["executed"] = json!(true));
+        assert_eq!(
+            text,
+            "{\n  \"a\": {\"y\": 2, \"b\": [1, 2]},\n  \"executed\": true\n}\n"
+        );
+    }
+
+    #[test]
+    fn splice_appends_new_fields_with_original_indent() {
+        let content = "{\n    \"a\": [1,2,  3]\n}";
+        let text = update(content, |v| v["executed_occurrences"] = json!(["x"]));
+        assert_eq!(
+            text,
+            "{\n    \"a\": [1,2,  3],\n    \"executed_occurrences\": [\n        \"x\"\n    ]\n}"
+        );
+    }
+
+    #[test]
+    fn splice_removes_fields() {
+        let content = "{\n  \"a\": 1,\n  \"b\": 2,\n  \"c\": 3\n}";
+        let text = update(content, |v| {
+            v.as_object_mut().unwrap().shift_remove("b");
+        });
+        assert_eq!(text, "{\n  \"a\": 1,\n  \"c\": 3\n}");
+        let text = update(content, |v| {
+            v.as_object_mut().unwrap().shift_remove("c");
+        });
+        assert_eq!(text, "{\n  \"a\": 1,\n  \"b\": 2\n}");
+    }
+
+    #[test]
+    fn splice_edits_inline_objects_inline() {
+        let content = "{\n  \"body\": {\"title\": \"t\", \"n\": 1}\n}";
+        let text = update(content, |v| {
+            v["body"]["n"] = json!(2);
+            v["body"]["k"] = json!({"x": [1]});
+        });
+        assert_eq!(
+            text,
+            "{\n  \"body\": {\"title\": \"t\", \"n\": 2, \"k\": {\"x\":[1]}}\n}"
+        );
+    }
+
+    #[test]
+    fn splice_keeps_crlf() {
+        let content = "{\r\n  \"a\": 1\r\n}\r\n";
+        let text = update(content, |v| v["b"] = json!({"c": 1}));
+        assert_eq!(
+            text,
+            "{\r\n  \"a\": 1,\r\n  \"b\": {\r\n    \"c\": 1\r\n  }\r\n}\r\n"
+        );
+    }
+
+    #[test]
+    fn splice_fills_empty_objects() {
+        let content = "{\n  \"headers\": {}\n}";
+        let text = update(content, |v| v["headers"]["X"] = json!("1"));
+        assert_eq!(text, "{\n  \"headers\": {\n    \"X\": \"1\"\n  }\n}");
+    }
+
+    #[test]
+    fn splice_falls_back_on_duplicate_keys() {
+        let content = "{\"a\": 1, \"a\": 2}";
+        assert_eq!(
+            splice(
+                content,
+                &JsonStyle::detect(content),
+                &json!({"a": 2}),
+                &json!({"a": 3})
+            ),
+            None
+        );
+    }
+}
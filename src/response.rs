@@ -0,0 +1,223 @@
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use reqwest::header::{HeaderMap, CONTENT_TYPE, RETRY_AFTER};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// 一次请求的响应
+///
+/// 可以按 `status, text = response` 解包, 兼容旧版 send_request 返回的元组。
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct Response {
+    #[pyo3(get)]
+    pub status: u16,
+    pub headers: HeaderMap,
+    /// 跟随重定向后的最终地址
+    #[pyo3(get)]
+    pub url: String,
+    /// 从发出第一个请求到读完响应体的耗时 (含重定向)
+    pub elapsed: Duration,
+    /// 跟随的重定向次数
+    #[pyo3(get)]
+    pub redirects: u32,
+    pub body: Vec<u8>,
+    /// 按 Content-Type 中的 charset (默认 UTF-8) 解码后的响应体
+    #[pyo3(get)]
+    pub text: String,
+    /// 写入执行历史失败的原因 (send_request 的 journal)
+    #[pyo3(get)]
+    pub journal_error: Option<String>,
+}
+
+impl Response {
+    /// 读取完整的响应体; 读取失败时返回错误而不是空字符串
+    pub(crate) fn read(
+        response: reqwest::blocking::Response,
+        elapsed_from: std::time::Instant,
+        redirects: u32,
+    ) -> reqwest::Result<Self> {
+        let status = response.status().as_u16();
+        let headers = response.headers().clone();
+        let url = response.url().to_string();
+        let body = response.bytes()?.to_vec();
+        let text = decode(&headers, &body);
+        Ok(Response {
+            status,
+            headers,
+            url,
+            elapsed: elapsed_from.elapsed(),
+            redirects,
+            body,
+            text,
+            journal_error: None,
+        })
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// 按名称取响应头 (不区分大小写), 多个同名响应头以 ", " 连接
+    pub fn header(&self, name: &str) -> Option<String> {
+        let values: Vec<&str> = self
+            .headers
+            .get_all(name)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .collect();
+        (!values.is_empty()).then(|| values.join(", "))
+    }
+
+    /// 解析 Retry-After 响应头 (秒数或 HTTP 日期)
+    pub fn retry_after(&self) -> Option<Duration> {
+        let value = self.headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+        if let Ok(secs) = value.parse::<u64>() {
+            return Some(Duration::from_secs(secs));
+        }
+        let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+        (at.with_timezone(&chrono::Utc) - chrono::Utc::now())
+            .to_std()
+            .ok()
+    }
+}
+
+// 按 Content-Type 的 charset 解码, 未知编码时按 UTF-8 处理
+fn decode(headers: &HeaderMap, body: &[u8]) -> String {
+    let charset = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|ct| {
+            ct.split(';').skip(1).find_map(|param| {
+                let (key, value) = param.split_once('=')?;
+                key.trim()
+                    .eq_ignore_ascii_case("charset")
+                    .then(|| value.trim().trim_matches('"').to_string())
+            })
+        });
+    let encoding = charset
+        .and_then(|c| encoding_rs::Encoding::for_label(c.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    encoding.decode(body).0.into_owned()
+}
+
+#[pymethods]
+impl Response {
+    /// 是否为 2xx
+    #[getter]
+    fn ok(&self) -> bool {
+        self.is_success()
+    }
+
+    /// 响应头字典, 键为小写
+    #[getter]
+    fn get_headers(&self) -> BTreeMap<String, String> {
+        self.headers
+            .keys()
+            .filter_map(|name| Some((name.to_string(), self.header(name.as_str())?)))
+            .collect()
+    }
+
+    #[getter]
+    fn get_elapsed_ms(&self) -> u64 {
+        self.elapsed.as_millis() as u64
+    }
+
+    /// 原始响应体
+    #[getter]
+    fn content<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.body)
+    }
+
+    #[pyo3(name = "header")]
+    fn py_header(&self, name: &str) -> Option<String> {
+        self.header(name)
+    }
+
+    /// 把响应体解析为 JSON, 格式错误时抛出 ValueError
+    fn json(&self, py: Python) -> PyResult<PyObject> {
+        let value: Value = serde_json::from_slice(&self.body).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("响应不是合法的 JSON: {}", e))
+        })?;
+        pythonize::pythonize(py, &value)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+    }
+
+    fn __iter__(&self, py: Python) -> PyResult<PyObject> {
+        let pair: PyObject = (self.status, self.text.clone()).into_py(py);
+        Ok(pair.as_ref(py).iter()?.into_py(py))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Response(status={}, url='{}')", self.status, self.url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn response(headers: &[(&'static str, &str)], body: &[u8]) -> Response {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+        Response {
+            status: 200,
+            text: decode(&map, body),
+            headers: map,
+            url: "http://example.com/".to_string(),
+            elapsed: Duration::ZERO,
+            redirects: 0,
+            body: body.to_vec(),
+            journal_error: None,
+        }
+    }
+
+    fn text(content_type: &str, body: &[u8]) -> String {
+        response(&[("content-type", content_type)], body).text
+    }
+
+    #[test]
+    fn decodes_by_charset() {
+        // "你好" 的 GBK 编码
+        let gbk = [0xc4, 0xe3, 0xba, 0xc3];
+        assert_eq!(text("text/plain; charset=gbk", &gbk), "你好");
+        assert_eq!(text("text/plain;CHARSET=\"GBK\"", &gbk), "你好");
+        assert_eq!(text("text/html; charset=iso-8859-1", &[0xe9]), "é");
+        // 未声明或未知的编码按 UTF-8 处理, 非法字节替换为 U+FFFD
+        assert_eq!(text("application/json", "你好".as_bytes()), "你好");
+        assert_eq!(
+            text("text/plain; charset=klingon", "你好".as_bytes()),
+            "你好"
+        );
+        assert_eq!(response(&[], &[0x61, 0xff]).text, "a\u{fffd}");
+    }
+
+    #[test]
+    fn joins_repeated_headers() {
+        let r = response(&[("x-a", "1"), ("X-A", "2")], b"");
+        assert_eq!(r.header("x-a").as_deref(), Some("1, 2"));
+        assert_eq!(r.header("x-b"), None);
+    }
+
+    fn retry_after(value: &str) -> Option<Duration> {
+        response(&[("retry-after", value)], b"").retry_after()
+    }
+
+    #[test]
+    fn parses_retry_after() {
+        assert_eq!(retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(retry_after(" 0 "), Some(Duration::ZERO));
+        let at = chrono::Utc::now() + chrono::Duration::seconds(90);
+        let delay = retry_after(&at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()).unwrap();
+        assert!(delay > Duration::from_secs(85) && delay <= Duration::from_secs(90));
+        // 已过去的日期、负数与无法解析的值都忽略
+        for bad in ["Sun, 06 Nov 1994 08:49:37 GMT", "-5", "1.5", "soon", ""] {
+            assert_eq!(retry_after(bad), None, "{}", bad);
+        }
+        assert_eq!(response(&[], b"").retry_after(), None);
+    }
+}
@@ -0,0 +1,300 @@
+use crate::config::duration_format;
+use crate::http::{RequestError, NETWORK_ERROR_KINDS};
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+
+/// 哪些状态码需要重试: 具体状态码 (429) 或一类状态码 ("5xx")
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "Value", into = "Value")]
+pub enum StatusPattern {
+    Code(u16),
+    /// 百位数字, 5 表示 5xx
+    Class(u16),
+}
+
+impl StatusPattern {
+    pub fn matches(&self, status: u16) -> bool {
+        match self {
+            StatusPattern::Code(code) => *code == status,
+            StatusPattern::Class(class) => status / 100 == *class,
+        }
+    }
+}
+
+impl fmt::Display for StatusPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatusPattern::Code(code) => write!(f, "{}", code),
+            StatusPattern::Class(class) => write!(f, "{}xx", class),
+        }
+    }
+}
+
+impl TryFrom<Value> for StatusPattern {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, String> {
+        let err = || {
+            format!(
+                "状态码格式错误 {}: 应为 100-599 的整数或 \"5xx\" 形式",
+                value
+            )
+        };
+        let pattern = match &value {
+            Value::Number(n) => StatusPattern::Code(
+                n.as_u64()
+                    .and_then(|n| u16::try_from(n).ok())
+                    .ok_or_else(err)?,
+            ),
+            Value::String(s) => match s.to_lowercase().strip_suffix("xx") {
+                Some(class) => StatusPattern::Class(class.parse().map_err(|_| err())?),
+                None => StatusPattern::Code(s.parse().map_err(|_| err())?),
+            },
+            _ => return Err(err()),
+        };
+        let valid = match pattern {
+            StatusPattern::Code(code) => (100..600).contains(&code),
+            StatusPattern::Class(class) => (1..6).contains(&class),
+        };
+        if valid {
+            Ok(pattern)
+        } else {
+            Err(err())
+        }
+    }
+}
+
+impl From<StatusPattern> for Value {
+    fn from(p: StatusPattern) -> Value {
+        match p {
+            StatusPattern::Code(code) => Value::from(code),
+            StatusPattern::Class(_) => Value::from(p.to_string()),
+        }
+    }
+}
+
+/// 发送失败时的重试策略 (任务配置中的 `retry` 字段, 所有字段均可省略)
+///
+/// ```json
+/// "retry": { "max_attempts": 5, "initial_delay": "2s", "max_delay": "1m",
+///            "max_elapsed": "5m", "retry_on": [408, 429, "5xx"] }
+/// ```
+///
+/// 第 n 次失败后等待 `initial_delay * multiplier^(n-1)` (不超过 `max_delay`),
+/// 再按 `jitter` 随机浮动; 响应带 Retry-After 时以其为准。
+/// 网络错误只有类别命中 `retry_on_errors` 时才重试 (默认不重试 TLS 证书错误),
+/// 状态码只有命中 `retry_on` 时才重试。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// 最多尝试次数 (含第一次)
+    pub max_attempts: u32,
+    #[serde(with = "duration_format")]
+    pub initial_delay: Duration,
+    pub multiplier: f64,
+    #[serde(with = "duration_format")]
+    pub max_delay: Duration,
+    /// 随机浮动比例, 0.2 表示 ±20%
+    pub jitter: f64,
+    /// 从第一次尝试起的总时长上限, 超过时不再重试
+    #[serde(with = "duration_format")]
+    pub max_elapsed: Duration,
+    pub retry_on: Vec<StatusPattern>,
+    /// 需要重试的网络错误类别, 取值见 NETWORK_ERROR_KINDS
+    pub retry_on_errors: Vec<String>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::seconds(2),
+            multiplier: 2.0,
+            max_delay: Duration::seconds(60),
+            jitter: 0.2,
+            max_elapsed: Duration::minutes(5),
+            retry_on: vec![
+                StatusPattern::Code(408),
+                StatusPattern::Code(429),
+                StatusPattern::Class(5),
+            ],
+            retry_on_errors: ["timeout", "dns", "refused", "connect", "body", "request"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 检查取值范围, 供配置校验使用
+    pub fn check(&self) -> Result<(), String> {
+        if self.max_attempts == 0 {
+            return Err("max_attempts 至少为 1".to_string());
+        }
+        if self.multiplier.is_nan() || self.multiplier < 1.0 {
+            return Err(format!("multiplier 不能小于 1, 实际为 {}", self.multiplier));
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            return Err(format!("jitter 应在 0 到 1 之间, 实际为 {}", self.jitter));
+        }
+        if let Some(kind) = self
+            .retry_on_errors
+            .iter()
+            .find(|k| !NETWORK_ERROR_KINDS.contains(&k.as_str()))
+        {
+            return Err(format!(
+                "retry_on_errors 中的错误类别无效 {}: 可选 {}",
+                kind,
+                NETWORK_ERROR_KINDS.join("/")
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn is_retryable_status(&self, status: u16) -> bool {
+        self.retry_on.iter().any(|p| p.matches(status))
+    }
+
+    pub fn is_retryable_error(&self, e: &RequestError) -> bool {
+        e.is_network() && self.retry_on_errors.iter().any(|k| k == e.kind())
+    }
+
+    /// 第 `attempt` 次尝试失败后的等待时间
+    pub fn delay(
+        &self,
+        attempt: u32,
+        retry_after: Option<std::time::Duration>,
+    ) -> std::time::Duration {
+        if let Some(d) = retry_after {
+            return d;
+        }
+        let exp = self
+            .multiplier
+            .max(1.0)
+            .powi(attempt.saturating_sub(1) as i32);
+        let base = (self.initial_delay.num_milliseconds() as f64 * exp)
+            .min(self.max_delay.num_milliseconds() as f64);
+        let jitter = self.jitter.clamp(0.0, 1.0) * (2.0 * random_unit() - 1.0);
+        std::time::Duration::from_millis((base * (1.0 + jitter)).max(0.0) as u64)
+    }
+
+    /// 已经过 `elapsed` 后再等待 `delay` 是否仍在 max_elapsed 之内
+    pub fn within_budget(&self, elapsed: std::time::Duration, delay: std::time::Duration) -> bool {
+        // Retry-After 可能极大, 相加溢出时视为超出
+        elapsed
+            .checked_add(delay)
+            .is_some_and(|t| t <= self.max_elapsed.to_std().unwrap_or_default())
+    }
+}
+
+// [0, 1) 之间的随机数; 抖动只需要打散请求, 不需要密码学强度
+fn random_unit() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default(),
+    );
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration as StdDuration;
+
+    fn policy(value: Value) -> RetryPolicy {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn status_patterns() {
+        let parse = |v| serde_json::from_value::<StatusPattern>(v);
+        assert_eq!(parse(json!(429)).unwrap(), StatusPattern::Code(429));
+        assert_eq!(parse(json!("404")).unwrap(), StatusPattern::Code(404));
+        assert_eq!(parse(json!("5xx")).unwrap(), StatusPattern::Class(5));
+        assert_eq!(parse(json!("4XX")).unwrap(), StatusPattern::Class(4));
+        // 超出 u16 的数字不能被截断为合法状态码 (65736 = 65536 + 200)
+        for bad in [
+            json!(600),
+            json!(99),
+            json!(65736),
+            json!("65736"),
+            json!(-200),
+            json!(200.5),
+            json!("6xx"),
+            json!("65741xx"),
+            json!("x"),
+            json!(true),
+        ] {
+            assert!(parse(bad.clone()).is_err(), "{}", bad);
+        }
+
+        assert!(StatusPattern::Class(5).matches(503));
+        assert!(!StatusPattern::Class(5).matches(429));
+        assert!(StatusPattern::Code(429).matches(429));
+        assert_eq!(
+            serde_json::to_value([StatusPattern::Code(408), StatusPattern::Class(5)]).unwrap(),
+            json!([408, "5xx"])
+        );
+    }
+
+    #[test]
+    fn default_policy_retries_408_429_5xx() {
+        let policy = RetryPolicy::default();
+        for status in [408, 429, 500, 503] {
+            assert!(policy.is_retryable_status(status), "{}", status);
+        }
+        for status in [400, 401, 404] {
+            assert!(!policy.is_retryable_status(status), "{}", status);
+        }
+        assert_eq!(policy.check(), Ok(()));
+    }
+
+    #[test]
+    fn delay_grows_exponentially_up_to_max() {
+        let policy = policy(json!({
+            "initial_delay": "2s", "multiplier": 3, "max_delay": "1m", "jitter": 0,
+        }));
+        let delays: Vec<u64> = (1..=5).map(|n| policy.delay(n, None).as_secs()).collect();
+        assert_eq!(delays, [2, 6, 18, 54, 60]);
+        // Retry-After 优先
+        assert_eq!(
+            policy.delay(1, Some(StdDuration::from_secs(7))),
+            StdDuration::from_secs(7)
+        );
+    }
+
+    #[test]
+    fn delay_jitter_stays_in_range() {
+        let policy = policy(json!({"initial_delay": "10s", "jitter": 0.2}));
+        for _ in 0..100 {
+            let ms = policy.delay(1, None).as_millis();
+            assert!((8_000..=12_000).contains(&ms), "{}", ms);
+        }
+    }
+
+    #[test]
+    fn budget_and_check() {
+        let budget = policy(json!({"max_elapsed": "1m"}));
+        assert!(budget.within_budget(StdDuration::from_secs(50), StdDuration::from_secs(10)));
+        assert!(!budget.within_budget(StdDuration::from_secs(50), StdDuration::from_secs(11)));
+        assert!(!budget.within_budget(StdDuration::from_secs(50), StdDuration::MAX));
+        let huge = budget.delay(1, Some(StdDuration::from_secs(u64::MAX)));
+        assert!(!budget.within_budget(StdDuration::ZERO, huge));
+
+        assert!(policy(json!({"max_attempts": 0})).check().is_err());
+        assert!(policy(json!({"multiplier": 0.5})).check().is_err());
+        assert!(policy(json!({"jitter": 1.5})).check().is_err());
+        assert!(policy(json!({"retry_on_errors": ["cert"]}))
+            .check()
+            .is_err());
+        assert!(serde_json::from_value::<RetryPolicy>(json!({"max_delay": "1 minute"})).is_err());
+    }
+}
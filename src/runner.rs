@@ -0,0 +1,544 @@
+use crate::body::BodyType;
+use crate::client::HttpClient;
+use crate::config::TaskConfig;
+use crate::due::{evaluate, DueResult, DueState};
+use crate::history::{self, AttemptRecord};
+use crate::http::{RequestError, RequestSpec};
+use crate::response::Response;
+use crate::retry::RetryPolicy;
+use crate::state::StateStore;
+use chrono::{DateTime, Duration, Utc};
+use pyo3::prelude::*;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// 存放推送 Key 的环境变量 (JSON 数组或 {别名: Key} 对象)
+pub const ENV_KEY_NAME: &str = "DEVICE_KEYS";
+
+/// 一轮调度的参数, 默认值与 Python 版 process_tasks 相同
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    pub config_dir: String,
+    pub state_path: PathBuf,
+    /// 为 None 时不写执行历史
+    pub history_path: Option<PathBuf>,
+    pub tolerance: Duration,
+    pub timeout_secs: u64,
+    /// 任务未配置 retry 时使用的重试策略
+    pub retry: RetryPolicy,
+    /// 注入 body.device_keys 的 Key, 见 inject_keys
+    pub secret_keys: Value,
+    /// 所有任务与重试共用的 Client
+    pub client: HttpClient,
+    /// 同时处理的任务数, 1 为逐个处理
+    pub concurrency: usize,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        RunOptions {
+            config_dir: "configs".to_string(),
+            state_path: PathBuf::from(crate::state::DEFAULT_STATE_PATH),
+            history_path: Some(PathBuf::from(crate::history::DEFAULT_HISTORY_PATH)),
+            tolerance: Duration::minutes(30),
+            timeout_secs: 20,
+            retry: RetryPolicy::default(),
+            secret_keys: Value::Null,
+            client: HttpClient::shared(),
+            concurrency: 1,
+        }
+    }
+}
+
+/// 从环境变量读取 Key, 未设置时为空数组
+pub fn load_secret_keys(var: &str) -> Result<Value, String> {
+    match std::env::var(var) {
+        Ok(raw) => {
+            serde_json::from_str(&raw).map_err(|e| format!("环境变量 {} JSON 格式错误: {}", var, e))
+        }
+        Err(_) => Ok(Value::Array(Vec::new())),
+    }
+}
+
+/// 把 Key 注入 body.device_keys, 规则与 Python 版相同:
+///
+/// - 数组: 追加到配置中的 device_keys 后并去重
+/// - 对象: 配置为空时注入全部 Key, 否则把别名替换为对应的 Key
+///
+/// body 不是对象时 (如 text) 原样返回。
+pub fn inject_keys(body: &Value, secret_keys: &Value) -> Value {
+    let Value::Object(body) = body else {
+        return body.clone();
+    };
+    let mut payload = body.clone();
+    let mut keys = match payload.get("device_keys") {
+        Some(Value::Array(items)) => items.clone(),
+        _ => Vec::new(),
+    };
+    match secret_keys {
+        Value::Array(secrets) if !secrets.is_empty() => {
+            for key in secrets {
+                if !keys.contains(key) {
+                    keys.push(key.clone());
+                }
+            }
+        }
+        Value::Object(secrets) => {
+            keys = if keys.is_empty() {
+                secrets.values().cloned().collect()
+            } else {
+                keys.into_iter()
+                    .map(|item| {
+                        item.as_str()
+                            .and_then(|alias| secrets.get(alias))
+                            .cloned()
+                            .unwrap_or(item)
+                    })
+                    .collect()
+            };
+        }
+        _ => {}
+    }
+    payload.insert("device_keys".to_string(), Value::Array(keys));
+    Value::Object(payload)
+}
+
+/// 发送一次请求并生成对应的历史记录
+///
+/// 记录中的 url 为 spec.url, 不含放在查询参数中的 API Key; 错误信息中的 URL 已隐藏查询参数的值。
+pub fn send_recorded(
+    client: &HttpClient,
+    spec: &RequestSpec,
+    attempt: u32,
+) -> (AttemptRecord, Result<Response, RequestError>) {
+    let mut record = AttemptRecord::new(&spec.method, &spec.url, attempt);
+    let started = Instant::now();
+    let result = client.send(spec);
+    record.latency_ms = started.elapsed().as_millis() as u64;
+    match &result {
+        Ok(response) => {
+            record.status = Some(response.status);
+            record.set_response(&response.text);
+            if let Some(expect) = &spec.expect {
+                record.set_accepted(expect.check(response));
+            }
+        }
+        Err(e) => record.set_error(e),
+    }
+    (record, result)
+}
+
+/// 按重试策略发送, 返回每次尝试的记录和最后一次的结果
+///
+/// 成功 (见 AttemptRecord::ok) 或不可重试的状态码/错误立即返回; `on_attempt` 在每次尝试后调用, 可用于补充记录并写入历史。
+pub fn send_with_retry(
+    client: &HttpClient,
+    spec: &RequestSpec,
+    policy: &RetryPolicy,
+    mut on_attempt: impl FnMut(&mut AttemptRecord),
+) -> (Vec<AttemptRecord>, Result<Response, RequestError>) {
+    let started = Instant::now();
+    let mut attempts = Vec::new();
+    let mut attempt = 1;
+    loop {
+        let (mut record, result) = send_recorded(client, spec, attempt);
+        on_attempt(&mut record);
+        let ok = record.ok();
+        attempts.push(record);
+        if ok {
+            return (attempts, result);
+        }
+        // 收到响应但不满足断言时, 只有状态码命中 retry_on 才重试
+        let retry_after = match &result {
+            Ok(response) if policy.is_retryable_status(response.status) => response.retry_after(),
+            Err(e) if policy.is_retryable_error(e) => None,
+            _ => return (attempts, result),
+        };
+        if attempt >= policy.max_attempts {
+            return (attempts, result);
+        }
+        let delay = policy.delay(attempt, retry_after);
+        if !policy.within_budget(started.elapsed(), delay) {
+            return (attempts, result);
+        }
+        std::thread::sleep(delay);
+        attempt += 1;
+    }
+}
+
+/// 一次触发 (含重试) 的结果
+#[derive(Debug, Clone)]
+pub struct FireOutcome {
+    pub attempts: Vec<AttemptRecord>,
+    pub success: bool,
+}
+
+/// 触发任务: 注入 Key 后按任务的 retry (或运行器默认策略) 发送
+///
+/// 只有 body_type 为 json 时注入 Key; form / multipart / raw 的 body 原样发送。
+/// 每次尝试都会写入执行历史 (若 history_path 非空)。
+pub fn fire(config: &TaskConfig, opts: &RunOptions) -> FireOutcome {
+    let payload = match config.body_type {
+        BodyType::Json => inject_keys(&config.body, &opts.secret_keys),
+        _ => config.body.clone(),
+    };
+    let policy = config.retry.as_ref().unwrap_or(&opts.retry);
+    let task_id = config.task_id();
+    let on_attempt = |record: &mut AttemptRecord| {
+        record.task_id = Some(task_id.clone());
+        record.task_name = config.task_name.clone();
+        if let Some(path) = &opts.history_path {
+            history::journal(path, record);
+        }
+    };
+    let spec = RequestSpec::new(
+        &config.method,
+        &config.webhook_url,
+        payload,
+        opts.timeout_secs,
+    )
+    .with_body_type(config.body_type)
+    .with_expect(config.expect.clone())
+    .with_query(config.query.clone().map(Value::Object))
+    .with_auth(&config.headers, config.auth.as_ref());
+    let attempts = match spec {
+        Ok(spec) => send_with_retry(&opts.client, &spec, policy, on_attempt).0,
+        // 环境变量缺失等配置问题: 不发送, 只记录一次失败
+        Err(e) => {
+            let mut record = AttemptRecord::new(&config.method, &config.webhook_url, 1);
+            record.set_error(&e);
+            on_attempt(&mut record);
+            vec![record]
+        }
+    };
+    FireOutcome {
+        success: attempts.last().is_some_and(AttemptRecord::ok),
+        attempts,
+    }
+}
+
+/// 单个配置文件在一轮调度中的结果
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct TaskReport {
+    #[pyo3(get)]
+    pub file: String,
+    /// 配置读取失败时为 None
+    pub config: Option<TaskConfig>,
+    #[pyo3(get)]
+    pub due: Option<DueResult>,
+    /// 只有到期的任务才会触发
+    pub outcome: Option<FireOutcome>,
+    #[pyo3(get)]
+    pub error: Option<String>,
+}
+
+#[pymethods]
+impl TaskReport {
+    #[getter]
+    fn get_task(&self) -> Option<TaskConfig> {
+        self.config.clone()
+    }
+
+    #[getter]
+    fn get_task_id(&self) -> Option<String> {
+        self.config.as_ref().map(TaskConfig::task_id)
+    }
+
+    #[getter]
+    fn get_task_name(&self) -> Option<String> {
+        self.config.as_ref().and_then(|c| c.task_name.clone())
+    }
+
+    /// 配置读取失败时为 None
+    #[getter]
+    fn get_state(&self) -> Option<DueState> {
+        self.due.as_ref().map(|d| d.state)
+    }
+
+    /// 是否发送了请求
+    #[getter]
+    fn fired(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    /// 未发送时为 None
+    #[getter]
+    fn get_success(&self) -> Option<bool> {
+        self.outcome.as_ref().map(|o| o.success)
+    }
+
+    /// 每次尝试的记录, 未发送时为空
+    #[getter]
+    fn get_attempts(&self) -> Vec<AttemptRecord> {
+        self.outcome
+            .as_ref()
+            .map(|o| o.attempts.clone())
+            .unwrap_or_default()
+    }
+
+    fn __repr__(&self) -> String {
+        let state = self
+            .due
+            .as_ref()
+            .map_or("None".to_string(), |d| format!("{:?}", d.state));
+        let success = match &self.outcome {
+            Some(o) if o.success => "True",
+            Some(_) => "False",
+            None => "None",
+        };
+        format!(
+            "TaskReport(file='{}', state={}, success={})",
+            self.file, state, success
+        )
+    }
+}
+
+/// 读取配置文件为 TaskConfig, 错误信息带字段路径
+pub fn load_config(path: &str) -> Result<TaskConfig, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("读取失败 {}: {}", path, e))?;
+    TaskConfig::parse(&content)
+        .map_err(|e| format!("配置格式错误 {} (字段 {}): {}", path, e.path(), e.inner()))
+}
+
+/// 读取状态文件, 错误信息带路径
+pub fn load_store(path: &Path) -> Result<StateStore, String> {
+    StateStore::load(path).map_err(|e| format!("读取状态失败 {}: {}", path.display(), e))
+}
+
+/// 判断单个任务是否到期, 到期则触发; 执行成功或有放弃的触发点时立即写回状态文件
+///
+/// 发送期间不持有 store 的锁, 其他任务可以同时判断与发送。
+pub fn process_task(
+    file: &str,
+    config: &TaskConfig,
+    store: &Mutex<StateStore>,
+    opts: &RunOptions,
+    now: DateTime<Utc>,
+) -> TaskReport {
+    let mut report = TaskReport {
+        file: file.to_string(),
+        config: Some(config.clone()),
+        due: None,
+        outcome: None,
+        error: None,
+    };
+    let (due, mut changed) = {
+        let mut store = store.lock().unwrap();
+        let due = evaluate(config, &store, now, opts.tolerance);
+        // 放弃的触发点先记下, 即使随后发送失败也不会再补发
+        let changed = store.record_skipped(config, &due);
+        (due, changed)
+    };
+    if due.state == DueState::Due {
+        let outcome = fire(config, opts);
+        changed |= outcome.success;
+        report.outcome = Some(outcome);
+    }
+    if changed {
+        let mut store = store.lock().unwrap();
+        if report.outcome.as_ref().is_some_and(|o| o.success) {
+            store.record(config, &due);
+        }
+        if let Err(e) = store.save(&opts.state_path) {
+            report.error = Some(format!("写入状态失败 {}: {}", opts.state_path.display(), e));
+        }
+    }
+    report.due = Some(due);
+    report
+}
+
+/// 执行一轮调度: 扫描配置、判断到期、触发并记录执行状态
+///
+/// 最多同时处理 `opts.concurrency` 个任务; 返回的结果按文件名排序,
+/// `on_report` 在每个任务处理完后立即调用 (并发时按完成顺序), 便于调用方实时输出。
+pub fn run_once(
+    opts: &RunOptions,
+    now: DateTime<Utc>,
+    on_report: impl FnMut(&TaskReport) + Send,
+) -> Result<Vec<TaskReport>, String> {
+    let store = Mutex::new(load_store(&opts.state_path)?);
+    let files = crate::scan_configs(&opts.config_dir);
+    let next = AtomicUsize::new(0);
+    let on_report = Mutex::new(on_report);
+    let reports = Mutex::new(Vec::with_capacity(files.len()));
+    let worker = || loop {
+        let index = next.fetch_add(1, Ordering::Relaxed);
+        let Some(file) = files.get(index) else {
+            break;
+        };
+        let report = match load_config(file) {
+            Ok(config) => process_task(file, &config, &store, opts, now),
+            Err(e) => TaskReport {
+                file: file.clone(),
+                config: None,
+                due: None,
+                outcome: None,
+                error: Some(e),
+            },
+        };
+        (on_report.lock().unwrap())(&report);
+        reports.lock().unwrap().push((index, report));
+    };
+    let workers = opts.concurrency.clamp(1, files.len().max(1));
+    if workers == 1 {
+        worker();
+    } else {
+        std::thread::scope(|s| {
+            for _ in 0..workers {
+                s.spawn(worker);
+            }
+        });
+    }
+    let mut reports = reports.into_inner().unwrap();
+    reports.sort_by_key(|(index, _)| *index);
+    Ok(reports.into_iter().map(|(_, report)| report).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{response, serve, serve_ok, Received};
+    use serde_json::json;
+
+    fn fire_body(body_type: &str, body: Value) -> (FireOutcome, Received) {
+        let (base, server) = serve_ok();
+        let url = format!("{}/hook", base);
+        let config = TaskConfig::parse(
+            &json!({
+                "trigger_time": "2026-01-02 22:00:00",
+                "webhook_url": url,
+                "body": body,
+                "body_type": body_type,
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let opts = RunOptions {
+            history_path: None,
+            secret_keys: json!(["k1"]),
+            retry: RetryPolicy {
+                max_attempts: 1,
+                ..RetryPolicy::default()
+            },
+            ..RunOptions::default()
+        };
+        let outcome = fire(&config, &opts);
+        let received = server.join().unwrap().remove(0);
+        assert_eq!(received.method, "POST");
+        assert_eq!(received.target, "/hook");
+        (outcome, received)
+    }
+
+    fn temp_file(name: &str, content: &str) -> String {
+        let path = std::env::temp_dir().join(format!("task_io_{}_{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn fire_json_injects_keys() {
+        let (outcome, received) = fire_body("json", json!({"title": "t"}));
+        assert!(outcome.success);
+        assert_eq!(received.header("content-type"), Some("application/json"));
+        let sent: Value = serde_json::from_slice(&received.body).unwrap();
+        assert_eq!(sent, json!({"title": "t", "device_keys": ["k1"]}));
+    }
+
+    #[test]
+    fn fire_form_sends_body_as_is() {
+        let (outcome, received) = fire_body("form", json!({"a": 1}));
+        assert!(outcome.success);
+        assert_eq!(
+            received.header("content-type"),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(received.text(), "a=1");
+    }
+
+    #[test]
+    fn fire_text_sends_body_as_is() {
+        let (outcome, received) = fire_body("text", json!("hello"));
+        assert!(outcome.success);
+        assert_eq!(received.text(), "hello");
+    }
+
+    #[test]
+    fn fire_raw_sends_file_content() {
+        let file = temp_file("raw.txt", "raw-content");
+        let (outcome, received) = fire_body("raw", json!({"file": file}));
+        std::fs::remove_file(&file).unwrap();
+        assert!(outcome.success, "{:?}", outcome.attempts);
+        assert_eq!(
+            received.header("content-type"),
+            Some("application/octet-stream")
+        );
+        assert_eq!(received.text(), "raw-content");
+    }
+
+    #[test]
+    fn fire_multipart_sends_fields_as_is() {
+        let file = temp_file("part.txt", "part-content");
+        let (outcome, received) = fire_body("multipart", json!({"a": "1", "f": {"file": file}}));
+        std::fs::remove_file(&file).unwrap();
+        let body = received.text();
+        assert!(outcome.success, "{:?}", outcome.attempts);
+        assert!(body.contains("name=\"a\""));
+        assert!(body.contains("part-content"));
+        assert!(!body.contains("device_keys"));
+    }
+
+    #[test]
+    fn inject_keys_appends_and_resolves_aliases() {
+        let body = json!({"device_keys": ["k1", "alias"]});
+        assert_eq!(
+            inject_keys(&body, &json!(["k1", "k2"])),
+            json!({"device_keys": ["k1", "alias", "k2"]})
+        );
+        assert_eq!(
+            inject_keys(&body, &json!({"alias": "real"})),
+            json!({"device_keys": ["k1", "real"]})
+        );
+        assert_eq!(inject_keys(&json!("text"), &json!(["k1"])), json!("text"));
+    }
+
+    #[test]
+    fn huge_retry_after_stops_retrying() {
+        let (base, server) = serve(1, |_| {
+            response(
+                "503 Service Unavailable",
+                &[("retry-after", "18446744073709551615")],
+                "",
+            )
+        });
+        let spec = RequestSpec::new("POST", &base, json!({}), 5);
+        let policy = RetryPolicy::default();
+        let (attempts, result) = send_with_retry(&HttpClient::shared(), &spec, &policy, |_| {});
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(result.unwrap().status, 503);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn history_write_failure_is_reported_on_the_record() {
+        let (base, server) = serve_ok();
+        let config = TaskConfig::parse(
+            &json!({"trigger_time": "2026-01-02 22:00:00", "webhook_url": format!("{}/hook", base)})
+                .to_string(),
+        )
+        .unwrap();
+        // 历史文件路径是一个目录, 追加必然失败
+        let opts = RunOptions {
+            history_path: Some(std::env::temp_dir()),
+            ..RunOptions::default()
+        };
+        let outcome = fire(&config, &opts);
+        server.join().unwrap();
+        assert!(outcome.success);
+        let error = outcome.attempts[0].journal_error.as_deref().unwrap();
+        assert!(error.starts_with("写入执行历史失败"), "{}", error);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::config::time_format;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use croner::Cron;
+use serde::{Deserialize, Serialize};
+
+/// 周期计划: 一个配置按 cron 表达式多次触发
+///
+/// ```json
+/// "schedule": { "cron": "0 22 * * 5", "start": "2026-01-02 00:00:00", "count": 13 }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    /// 5 段 (分 时 日 月 周) 或 6 段 (秒 分 时 日 月 周) cron 表达式
+    pub cron: String,
+    /// 计划使用的时区, 省略时沿用任务的 timezone
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// 本地时间, 早于此时间的触发点不计
+    #[serde(
+        default,
+        with = "time_format::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub start: Option<NaiveDateTime>,
+    /// 本地时间, 晚于此时间的触发点不计
+    #[serde(
+        default,
+        with = "time_format::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub end: Option<NaiveDateTime>,
+    /// 从 start 起最多触发的次数, 需要同时设置 start
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+}
+
+/// 解析 cron 表达式, 秒字段可选
+pub fn parse_cron(expr: &str) -> Result<Cron, String> {
+    Cron::new(expr)
+        .with_seconds_optional()
+        .parse()
+        .map_err(|e| format!("cron 表达式无效 '{}': {}", expr, e))
+}
+
+impl Schedule {
+    pub fn parse_cron(&self) -> Result<Cron, String> {
+        if self.count.is_some() && self.start.is_none() {
+            return Err("设置 count 时必须同时设置 start".to_string());
+        }
+        parse_cron(&self.cron)
+    }
+
+    /// 从 `from` (含) 起按本地时间依次产生触发点, 已考虑 start/end/count
+    ///
+    /// 设置了 count 时总是从 start 开始计数, `from` 之前的触发点会被跳过。
+    pub fn local_occurrences(
+        &self,
+        cron: &Cron,
+        from: NaiveDateTime,
+    ) -> impl Iterator<Item = NaiveDateTime> {
+        let begin = match (self.start, self.count) {
+            (Some(start), Some(_)) => start,
+            (Some(start), None) => start.max(from),
+            (None, _) => from,
+        };
+        let end = self.end;
+        let count = self.count.map(|c| c as usize).unwrap_or(usize::MAX);
+        // cron 在本地墙上时间上计算, 借用 Utc 作为"无时区"的载体,
+        // 本地时间到绝对时间的换算由调用方负责
+        cron.iter_from(Utc.from_utc_datetime(&begin))
+            .map(|t| t.naive_utc())
+            .take(count)
+            .take_while(move |t| end.is_none_or(|end| *t <= end))
+            .skip_while(move |t| *t < from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, crate::TIME_FORMAT).unwrap()
+    }
+
+    fn occurrences(schedule: &Schedule, from: &str, n: usize) -> Vec<String> {
+        let cron = schedule.parse_cron().unwrap();
+        schedule
+            .local_occurrences(&cron, naive(from))
+            .take(n)
+            .map(|t| t.format(crate::TIME_FORMAT).to_string())
+            .collect()
+    }
+
+    fn schedule(json: &str) -> Schedule {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn occurrences_respect_start_end_and_count() {
+        let weekly = schedule(r#"{"cron": "0 22 * * 5"}"#);
+        assert_eq!(
+            occurrences(&weekly, "2026-01-02 22:00:00", 2),
+            ["2026-01-02 22:00:00", "2026-01-09 22:00:00"]
+        );
+
+        let bounded = schedule(
+            r#"{"cron": "0 22 * * 5", "start": "2026-01-03 00:00:00", "end": "2026-01-16 22:00:00"}"#,
+        );
+        assert_eq!(
+            occurrences(&bounded, "2026-01-01 00:00:00", 10),
+            ["2026-01-09 22:00:00", "2026-01-16 22:00:00"]
+        );
+
+        // count 从 start 起计数, from 之前的触发点也占用次数
+        let counted =
+            schedule(r#"{"cron": "0 22 * * 5", "start": "2026-01-01 00:00:00", "count": 3}"#);
+        assert_eq!(
+            occurrences(&counted, "2026-01-05 00:00:00", 10),
+            ["2026-01-09 22:00:00", "2026-01-16 22:00:00"]
+        );
+    }
+
+    #[test]
+    fn cron_with_optional_seconds() {
+        assert!(parse_cron("0 22 * * 5").is_ok());
+        assert!(parse_cron("30 0 22 * * 5").is_ok());
+        assert!(parse_cron("not a cron").is_err());
+        let counted = schedule(r#"{"cron": "0 22 * * 5", "count": 3}"#);
+        assert!(counted.parse_cron().is_err());
+    }
+}
@@ -0,0 +1,350 @@
+use crate::atomic::write_atomic;
+use crate::config::{TaskConfig, TIME_FORMAT};
+use crate::due::DueResult;
+use crate::errors;
+use chrono::NaiveDateTime;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// 默认的执行状态文件
+pub const DEFAULT_STATE_PATH: &str = "state/executions.json";
+
+/// 单个任务的执行状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskState {
+    /// 仅供人阅读, 便于在状态文件中找到对应任务
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_name: Option<String>,
+    #[serde(default)]
+    pub executed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub executed_at: Option<String>,
+    /// 周期任务已执行的触发点 (本地时间, TIME_FORMAT)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub occurrences: Vec<String>,
+    /// 错过后按 misfire_policy 放弃的触发点 (本地时间, TIME_FORMAT)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+}
+
+/// 与任务定义分离的执行状态存储, 以 TaskConfig::task_id 为键
+///
+/// 机器人只写这个文件, configs/ 只由人编辑, 两边不会再互相冲突。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StateStore {
+    #[serde(default)]
+    pub tasks: BTreeMap<String, TaskState>,
+}
+
+impl StateStore {
+    /// 读取状态文件, 文件不存在时返回空状态
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(StateStore::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            if !dir.as_os_str().is_empty() {
+                fs::create_dir_all(dir)?;
+            }
+        }
+        let mut content = serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        content.push('\n');
+        write_atomic(path, content.as_bytes(), false)
+    }
+
+    /// 任务 (或周期任务的某个触发点) 是否已执行
+    ///
+    /// 兼容旧配置: 配置文件中内联的 executed / executed_occurrences 同样生效。
+    pub fn is_executed(&self, config: &TaskConfig, occurrence: Option<&NaiveDateTime>) -> bool {
+        match occurrence {
+            Some(occ) => self.is_occurrence_executed(config, &config.task_id(), occ),
+            None => {
+                config.executed
+                    || self
+                        .tasks
+                        .get(&config.task_id())
+                        .is_some_and(|s| s.executed)
+            }
+        }
+    }
+
+    /// 周期任务的某个触发点是否已执行
+    ///
+    /// `task_id` 为 `config.task_id()`, 逐个检查触发点时由调用方算好后复用。
+    pub fn is_occurrence_executed(
+        &self,
+        config: &TaskConfig,
+        task_id: &str,
+        occurrence: &NaiveDateTime,
+    ) -> bool {
+        let key = occurrence.format(TIME_FORMAT).to_string();
+        config.executed_occurrences.contains(&key)
+            || self
+                .tasks
+                .get(task_id)
+                .is_some_and(|s| s.occurrences.contains(&key))
+    }
+
+    /// 触发点是否已被记录为放弃, `task_id` 同 is_occurrence_executed
+    pub fn is_skipped(&self, task_id: &str, occurrence: &NaiveDateTime) -> bool {
+        let key = occurrence.format(TIME_FORMAT).to_string();
+        self.tasks
+            .get(task_id)
+            .is_some_and(|s| s.skipped.contains(&key))
+    }
+
+    /// 最近一个已执行或已放弃的触发点, 用于判断之后错过了哪些触发点
+    pub fn last_handled(&self, config: &TaskConfig) -> Option<NaiveDateTime> {
+        let state = self.tasks.get(&config.task_id());
+        let recorded = state
+            .into_iter()
+            .flat_map(|s| s.occurrences.iter().chain(&s.skipped));
+        config
+            .executed_occurrences
+            .iter()
+            .chain(recorded)
+            .filter_map(|k| NaiveDateTime::parse_from_str(k, TIME_FORMAT).ok())
+            .max()
+    }
+
+    /// 记录 evaluate 放弃的触发点; 有新增时返回 true
+    pub fn record_skipped(&mut self, config: &TaskConfig, due: &DueResult) -> bool {
+        if due.skipped.is_empty() {
+            return false;
+        }
+        let state = self.tasks.entry(config.task_id()).or_default();
+        state.task_name = config.task_name.clone();
+        let mut added = false;
+        for t in &due.skipped {
+            let key = t.naive_local().format(TIME_FORMAT).to_string();
+            if !state.skipped.contains(&key) {
+                state.skipped.push(key);
+                added = true;
+            }
+        }
+        added
+    }
+
+    /// 根据 evaluate 的结果记录一次成功执行, 同时记录放弃的触发点
+    pub fn record(&mut self, config: &TaskConfig, due: &DueResult) {
+        self.record_skipped(config, due);
+        let state = self.tasks.entry(config.task_id()).or_default();
+        state.task_name = config.task_name.clone();
+        if config.schedule.is_some() {
+            if let Some(t) = due.trigger_time {
+                let key = t.naive_local().format(TIME_FORMAT).to_string();
+                if !state.occurrences.contains(&key) {
+                    state.occurrences.push(key);
+                }
+            }
+        } else {
+            state.executed = true;
+        }
+        state.executed_at = Some(due.now_local());
+    }
+
+    /// 清除某个任务的状态, `config` 为 None 时清空全部; 返回清除的条目数
+    pub fn reset(&mut self, config: Option<&TaskConfig>) -> usize {
+        match config {
+            Some(c) => self.tasks.remove(&c.task_id()).map_or(0, |_| 1),
+            None => {
+                let n = self.tasks.len();
+                self.tasks.clear();
+                n
+            }
+        }
+    }
+}
+
+// 读写状态文件时释放 GIL
+fn load_store(py: Python, path: &str) -> PyResult<StateStore> {
+    py.allow_threads(|| StateStore::load(Path::new(path)))
+        .map_err(|e| errors::read_error(py, path, format!("读取状态失败 {}: {}", path, e), &e))
+}
+
+fn save_store(py: Python, store: &StateStore, path: &str) -> PyResult<()> {
+    py.allow_threads(|| store.save(Path::new(path)))
+        .map_err(|e| errors::write_error(py, path, format!("写入状态失败 {}: {}", path, e), &e))
+}
+
+// 查询任务是否已执行; occurrence 为周期任务的触发点 (YYYY-MM-DD HH:MM:SS)
+#[pyfunction]
+#[pyo3(signature = (path, task, occurrence=None))]
+pub fn is_executed(
+    path: String,
+    task: PyRef<TaskConfig>,
+    occurrence: Option<String>,
+    py: Python,
+) -> PyResult<bool> {
+    let occurrence = match occurrence {
+        Some(s) => Some(NaiveDateTime::parse_from_str(&s, TIME_FORMAT).map_err(|e| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("时间格式错误 {}: {}", s, e))
+        })?),
+        None => None,
+    };
+    Ok(load_store(py, &path)?.is_executed(&task, occurrence.as_ref()))
+}
+
+// 按 evaluate_due 的结果记录执行成功, 立即写回状态文件
+#[pyfunction]
+pub fn mark_executed(
+    path: String,
+    task: PyRef<TaskConfig>,
+    due: PyRef<DueResult>,
+    py: Python,
+) -> PyResult<()> {
+    let mut store = load_store(py, &path)?;
+    store.record(&task, &due);
+    save_store(py, &store, &path)
+}
+
+// 记录 evaluate_due 结果中放弃的触发点 (due.skipped), 有新增时写回并返回 True
+#[pyfunction]
+pub fn record_skipped(
+    path: String,
+    task: PyRef<TaskConfig>,
+    due: PyRef<DueResult>,
+    py: Python,
+) -> PyResult<bool> {
+    let mut store = load_store(py, &path)?;
+    let added = store.record_skipped(&task, &due);
+    if added {
+        save_store(py, &store, &path)?;
+    }
+    Ok(added)
+}
+
+// 清除任务的执行状态, task 为 None 时清空全部; 返回清除的条目数
+#[pyfunction]
+#[pyo3(signature = (path, task=None))]
+pub fn reset(path: String, task: Option<PyRef<TaskConfig>>, py: Python) -> PyResult<usize> {
+    let mut store = load_store(py, &path)?;
+    let n = store.reset(task.as_deref());
+    if n > 0 {
+        save_store(py, &store, &path)?;
+    }
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::due::evaluate;
+    use chrono::Duration;
+    use serde_json::json;
+
+    fn task(fields: serde_json::Value) -> TaskConfig {
+        let mut value = json!({"webhook_url": "https://example.com/hook", "timezone": "UTC"});
+        value
+            .as_object_mut()
+            .unwrap()
+            .extend(fields.as_object().unwrap().clone());
+        TaskConfig::parse(&value.to_string()).unwrap()
+    }
+
+    fn naive(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, TIME_FORMAT).unwrap()
+    }
+
+    fn eval(config: &TaskConfig, store: &StateStore, now: &str) -> DueResult {
+        evaluate(config, store, naive(now).and_utc(), Duration::minutes(30))
+    }
+
+    #[test]
+    fn load_and_save_round_trip() {
+        let dir = std::env::temp_dir().join(format!("task_io_state_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("state").join("executions.json");
+        assert!(StateStore::load(&path).unwrap().tasks.is_empty());
+
+        let config = task(json!({"task_name": "a", "trigger_time": "2026-01-02 22:00:00"}));
+        let mut store = StateStore::default();
+        store.record(&config, &eval(&config, &store, "2026-01-02 22:00:05"));
+        store.save(&path).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.ends_with("}\n"));
+
+        let loaded = StateStore::load(&path).unwrap();
+        let state = &loaded.tasks[&config.task_id()];
+        assert_eq!(state.task_name.as_deref(), Some("a"));
+        assert_eq!(state.executed_at.as_deref(), Some("2026-01-02 22:00:05"));
+        assert!(loaded.is_executed(&config, None));
+
+        fs::write(&path, "{").unwrap();
+        let err = StateStore::load(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn records_occurrences_and_skips() {
+        let config = task(json!({"schedule": {"cron": "0 22 * * *"}, "misfire_policy": "skip"}));
+        let id = config.task_id();
+        let mut store = StateStore::default();
+        let first = naive("2026-01-02 22:00:00");
+        store.record(&config, &eval(&config, &store, "2026-01-02 22:00:05"));
+        assert!(store.is_executed(&config, Some(&first)));
+        assert!(!store.is_executed(&config, None));
+        assert_eq!(store.last_handled(&config), Some(first));
+
+        // 错过 01-03 的触发点后, 01-04 检查时记为放弃, 重复记录不算新增
+        let due = eval(&config, &store, "2026-01-04 22:00:05");
+        assert!(store.record_skipped(&config, &due));
+        assert!(!store.record_skipped(&config, &due));
+        let skipped = naive("2026-01-03 22:00:00");
+        assert!(store.is_skipped(&id, &skipped));
+        assert!(!store.is_occurrence_executed(&config, &id, &skipped));
+
+        store.record(&config, &due);
+        let last = naive("2026-01-04 22:00:00");
+        assert!(store.is_occurrence_executed(&config, &id, &last));
+        assert_eq!(store.last_handled(&config), Some(last));
+        assert_eq!(store.tasks[&id].occurrences.len(), 2);
+        assert_eq!(store.tasks[&id].skipped, ["2026-01-03 22:00:00"]);
+    }
+
+    #[test]
+    fn inline_state_in_config_is_honoured() {
+        let once = task(json!({"trigger_time": "2026-01-02 22:00:00", "executed": true}));
+        assert!(StateStore::default().is_executed(&once, None));
+
+        let periodic = task(json!({
+            "schedule": {"cron": "0 22 * * *"},
+            "executed_occurrences": ["2026-01-05 22:00:00"],
+        }));
+        let mut store = StateStore::default();
+        store.record(&periodic, &eval(&periodic, &store, "2026-01-02 22:00:05"));
+        let inline = naive("2026-01-05 22:00:00");
+        assert!(store.is_executed(&periodic, Some(&inline)));
+        // 状态文件与配置中的记录一起决定最近的触发点
+        assert_eq!(store.last_handled(&periodic), Some(inline));
+    }
+
+    #[test]
+    fn reset_one_or_all() {
+        let a = task(json!({"trigger_time": "2026-01-02 22:00:00"}));
+        let b = task(json!({"trigger_time": "2026-01-03 22:00:00"}));
+        let mut store = StateStore::default();
+        for config in [&a, &b] {
+            let due = eval(config, &store, "2026-01-03 22:00:05");
+            store.record(config, &due);
+        }
+        assert_eq!(store.reset(Some(&a)), 1);
+        assert_eq!(store.reset(Some(&a)), 0);
+        assert!(!store.is_executed(&a, None));
+        assert!(store.is_executed(&b, None));
+        assert_eq!(store.reset(None), 1);
+        assert!(store.tasks.is_empty());
+    }
+}
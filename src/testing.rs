@@ -0,0 +1,107 @@
+//! 单元测试共用的本地 HTTP 服务
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::JoinHandle;
+
+/// 服务收到的一个请求
+#[derive(Debug, Clone)]
+pub struct Received {
+    pub method: String,
+    /// 路径与查询参数, 如 `/hook?a=1`
+    pub target: String,
+    /// 名称为小写
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Received {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// 构造完整的 HTTP 响应, 总是带 content-length 并关闭连接
+pub fn response(status: &str, headers: &[(&str, &str)], body: &str) -> String {
+    let mut out = format!("HTTP/1.1 {}\r\n", status);
+    for (name, value) in headers {
+        out.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    out.push_str(&format!(
+        "content-length: {}\r\nconnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    ));
+    out
+}
+
+/// 依次处理 `count` 个请求 (每个连接一个), `respond` 返回响应文本
+///
+/// 返回服务地址 (`http://127.0.0.1:端口`) 与收到的全部请求。
+pub fn serve(
+    count: usize,
+    respond: impl Fn(&Received) -> String + Send + 'static,
+) -> (String, JoinHandle<Vec<Received>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let handle = std::thread::spawn(move || {
+        let mut received = Vec::new();
+        for _ in 0..count {
+            let (mut stream, _) = listener.accept().unwrap();
+            let Some(request) = read_request(&mut stream) else {
+                continue;
+            };
+            stream.write_all(respond(&request).as_bytes()).unwrap();
+            received.push(request);
+        }
+        received
+    });
+    (base, handle)
+}
+
+/// 只处理一个请求并返回 200
+pub fn serve_ok() -> (String, JoinHandle<Vec<Received>>) {
+    serve(1, |_| response("200 OK", &[], ""))
+}
+
+fn read_request(stream: &mut TcpStream) -> Option<Received> {
+    let mut buf = Vec::new();
+    let mut chunk = [0; 4096];
+    loop {
+        let n = stream.read(&mut chunk).ok()?;
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&buf[..end]).into_owned();
+            let mut lines = head.split("\r\n");
+            let mut request_line = lines.next()?.split(' ');
+            let method = request_line.next()?.to_string();
+            let target = request_line.next()?.to_string();
+            let headers: Vec<(String, String)> = lines
+                .filter_map(|l| l.split_once(':'))
+                .map(|(n, v)| (n.trim().to_lowercase(), v.trim().to_string()))
+                .collect();
+            let len: usize = headers
+                .iter()
+                .find(|(n, _)| n == "content-length")
+                .map_or(0, |(_, v)| v.parse().unwrap_or(0));
+            if buf.len() >= end + 4 + len || n == 0 {
+                return Some(Received {
+                    method,
+                    target,
+                    headers,
+                    body: buf[end + 4..].to_vec(),
+                });
+            }
+        }
+        if n == 0 {
+            return None;
+        }
+    }
+}
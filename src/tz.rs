@@ -0,0 +1,207 @@
+use chrono::{DateTime, Duration, FixedOffset, LocalResult, NaiveDateTime, Offset, TimeZone};
+use chrono_tz::Tz;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// 夏令时切换时本地时间的处理方式
+///
+/// - 回拨造成的重复时间 (如 01:30 出现两次): earliest 取第一次, latest 取第二次
+/// - 拨快造成的不存在时间 (如 02:30 被跳过): earliest 按切换后的偏移换算 (落在切换前),
+///   latest 按切换前的偏移换算 (落在切换后)
+/// - reject: 两种情况都视为错误
+///
+/// 读取配置时不区分大小写, 与校验一致
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", try_from = "String")]
+pub enum DstPolicy {
+    #[default]
+    Earliest,
+    Latest,
+    Reject,
+}
+
+impl TryFrom<String> for DstPolicy {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, String> {
+        DstPolicy::parse(&s)
+    }
+}
+
+impl DstPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DstPolicy::Earliest => "earliest",
+            DstPolicy::Latest => "latest",
+            DstPolicy::Reject => "reject",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "earliest" => Ok(DstPolicy::Earliest),
+            "latest" => Ok(DstPolicy::Latest),
+            "reject" => Ok(DstPolicy::Reject),
+            _ => Err(format!(
+                "未知的 dst_policy: {} (可选: earliest/latest/reject)",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocalizeError {
+    /// 本地时间因夏令时拨快而不存在
+    Nonexistent(NaiveDateTime, Tz),
+    /// 本地时间因夏令时回拨而出现两次
+    Ambiguous(NaiveDateTime, Tz),
+}
+
+impl fmt::Display for LocalizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LocalizeError::Nonexistent(t, tz) => {
+                write!(f, "本地时间 {} 在时区 {} 中不存在 (夏令时跳过)", t, tz)
+            }
+            LocalizeError::Ambiguous(t, tz) => {
+                write!(f, "本地时间 {} 在时区 {} 中出现两次 (夏令时回拨)", t, tz)
+            }
+        }
+    }
+}
+
+/// 按 IANA 名称解析时区, 未知时区直接报错而不是回退到 UTC
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.parse::<Tz>()
+        .map_err(|_| format!("未知的 IANA 时区: {}", name))
+}
+
+/// 把本地时间换算为带时区的时间, 夏令时边界按 `policy` 处理
+pub fn localize(
+    tz: Tz,
+    local: NaiveDateTime,
+    policy: DstPolicy,
+) -> Result<DateTime<Tz>, LocalizeError> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(t) => Ok(t),
+        LocalResult::Ambiguous(first, second) => match policy {
+            DstPolicy::Earliest => Ok(first.min(second)),
+            DstPolicy::Latest => Ok(first.max(second)),
+            DstPolicy::Reject => Err(LocalizeError::Ambiguous(local, tz)),
+        },
+        LocalResult::None => {
+            // 切换点前后一天的偏移即为跳变前后的偏移
+            let before = tz
+                .offset_from_utc_datetime(&(local - Duration::days(1)))
+                .fix();
+            let after = tz
+                .offset_from_utc_datetime(&(local + Duration::days(1)))
+                .fix();
+            let offset = match policy {
+                DstPolicy::Earliest => after,
+                DstPolicy::Latest => before,
+                DstPolicy::Reject => return Err(LocalizeError::Nonexistent(local, tz)),
+            };
+            let utc = local - Duration::seconds(offset.local_minus_utc() as i64);
+            Ok(tz.from_utc_datetime(&utc))
+        }
+    }
+}
+
+// 把 "YYYY-MM-DD HH:MM:SS" 形式的本地时间换算为带时区的 datetime
+// dst_policy: earliest / latest / reject
+#[pyfunction]
+#[pyo3(signature = (local_time, timezone, dst_policy="earliest"))]
+pub fn localize_time(
+    local_time: String,
+    timezone: String,
+    dst_policy: &str,
+) -> PyResult<DateTime<FixedOffset>> {
+    let value_error = |msg: String| PyErr::new::<pyo3::exceptions::PyValueError, _>(msg);
+    let tz = parse_timezone(&timezone).map_err(value_error)?;
+    let policy = DstPolicy::parse(dst_policy).map_err(value_error)?;
+    let local = NaiveDateTime::parse_from_str(&local_time, crate::TIME_FORMAT)
+        .map_err(|e| value_error(format!("时间格式错误 {}: {}", local_time, e)))?;
+    let t = localize(tz, local, policy).map_err(|e| value_error(e.to_string()))?;
+    Ok(t.fixed_offset())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEW_YORK: Tz = chrono_tz::America::New_York;
+
+    fn naive(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, crate::TIME_FORMAT).unwrap()
+    }
+
+    // 换算后的 UTC 时间
+    fn to_utc(local: &str, policy: DstPolicy) -> Result<String, LocalizeError> {
+        localize(NEW_YORK, naive(local), policy)
+            .map(|t| t.naive_utc().format(crate::TIME_FORMAT).to_string())
+    }
+
+    #[test]
+    fn localize_regular_time() {
+        for policy in [DstPolicy::Earliest, DstPolicy::Latest, DstPolicy::Reject] {
+            assert_eq!(
+                to_utc("2026-01-02 22:00:00", policy).unwrap(),
+                "2026-01-03 03:00:00"
+            );
+        }
+    }
+
+    #[test]
+    fn localize_nonexistent_time() {
+        // 2026-03-08 02:00 EST 拨快到 03:00 EDT
+        assert_eq!(
+            to_utc("2026-03-08 02:30:00", DstPolicy::Earliest).unwrap(),
+            "2026-03-08 06:30:00"
+        );
+        assert_eq!(
+            to_utc("2026-03-08 02:30:00", DstPolicy::Latest).unwrap(),
+            "2026-03-08 07:30:00"
+        );
+        assert_eq!(
+            to_utc("2026-03-08 02:30:00", DstPolicy::Reject),
+            Err(LocalizeError::Nonexistent(
+                naive("2026-03-08 02:30:00"),
+                NEW_YORK
+            ))
+        );
+    }
+
+    #[test]
+    fn localize_ambiguous_time() {
+        // 2026-11-01 02:00 EDT 回拨到 01:00 EST, 01:30 出现两次
+        assert_eq!(
+            to_utc("2026-11-01 01:30:00", DstPolicy::Earliest).unwrap(),
+            "2026-11-01 05:30:00"
+        );
+        assert_eq!(
+            to_utc("2026-11-01 01:30:00", DstPolicy::Latest).unwrap(),
+            "2026-11-01 06:30:00"
+        );
+        assert_eq!(
+            to_utc("2026-11-01 01:30:00", DstPolicy::Reject),
+            Err(LocalizeError::Ambiguous(
+                naive("2026-11-01 01:30:00"),
+                NEW_YORK
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_names() {
+        assert_eq!(
+            parse_timezone("Asia/Shanghai"),
+            Ok(chrono_tz::Asia::Shanghai)
+        );
+        assert!(parse_timezone("Asia/Nowhere").is_err());
+        assert_eq!(DstPolicy::parse("LATEST"), Ok(DstPolicy::Latest));
+        assert!(DstPolicy::parse("first").is_err());
+    }
+}
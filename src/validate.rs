@@ -0,0 +1,996 @@
+use crate::auth::{is_env_ref, Auth};
+use crate::body::BodyType;
+use crate::config::{parse_duration, TaskConfig, TIME_FORMAT};
+use crate::expect::Expect;
+use crate::retry::RetryPolicy;
+use crate::tz::{localize, DstPolicy};
+use chrono::{NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use pyo3::prelude::*;
+use serde_json::{Map, Value};
+use serde_path_to_error::Segment;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// 标准 HTTP 方法; 其他合法的方法名 (如 PURGE) 也可以发送, 校验时给出警告
+pub const SUPPORTED_METHODS: &[&str] =
+    &["GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS"];
+
+// TaskConfig 认识的顶层字段, 其余字段只给出警告
+const KNOWN_FIELDS: &[&str] = &[
+    "id",
+    "task_name",
+    "trigger_time",
+    "timezone",
+    "webhook_url",
+    "method",
+    "body",
+    "body_type",
+    "query",
+    "executed",
+    "executed_at",
+    "schedule",
+    "executed_occurrences",
+    "dst_policy",
+    "misfire_policy",
+    "retry",
+    "expect",
+    "headers",
+    "auth",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// 一条校验结果: 哪个文件、哪个字段 (JSON Pointer)、严重程度和说明
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    #[pyo3(get)]
+    pub file: String,
+    #[pyo3(get)]
+    pub pointer: String,
+    pub severity: Severity,
+    #[pyo3(get)]
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn new(file: &str, pointer: String, severity: Severity, message: String) -> Self {
+        Diagnostic {
+            file: file.to_string(),
+            pointer,
+            severity,
+            message,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+#[pymethods]
+impl Diagnostic {
+    /// "error" 或 "warning"
+    #[getter]
+    fn get_severity(&self) -> &'static str {
+        self.severity.as_str()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Diagnostic(file='{}', pointer='{}', severity='{}', message='{}')",
+            self.file,
+            self.pointer,
+            self.severity.as_str(),
+            self.message
+        )
+    }
+
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pointer = if self.pointer.is_empty() {
+            "/"
+        } else {
+            &self.pointer
+        };
+        write!(
+            f,
+            "[{}] {} {}: {}",
+            self.severity.as_str(),
+            self.file,
+            pointer,
+            self.message
+        )
+    }
+}
+
+// 按 RFC 6901 转义一段 JSON Pointer
+fn pointer(segments: &[&str]) -> String {
+    segments
+        .iter()
+        .map(|s| format!("/{}", s.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+// 读取并解析为 JSON, 失败时转为一条 error 诊断
+fn load_value(path: &str) -> Result<Value, Diagnostic> {
+    let content = fs::read_to_string(path).map_err(|e| {
+        Diagnostic::new(
+            path,
+            String::new(),
+            Severity::Error,
+            format!("读取失败: {}", e),
+        )
+    })?;
+    serde_json::from_str(&content).map_err(|e| {
+        Diagnostic::new(
+            path,
+            String::new(),
+            Severity::Error,
+            format!("JSON 格式错误: {}", e),
+        )
+    })
+}
+
+/// 校验单个配置文件
+pub fn validate_file(path: &str) -> Vec<Diagnostic> {
+    match load_value(path) {
+        Ok(v) => validate_value(path, &v),
+        Err(d) => vec![d],
+    }
+}
+
+/// 校验已解析的配置内容, `file` 仅用于填充诊断信息
+pub fn validate_value(file: &str, value: &Value) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+    let obj = match value.as_object() {
+        Some(o) => o,
+        None => {
+            out.push(Diagnostic::new(
+                file,
+                String::new(),
+                Severity::Error,
+                "配置必须是 JSON 对象".to_string(),
+            ));
+            return out;
+        }
+    };
+    let mut error = |field: &str, message: String| {
+        out.push(Diagnostic::new(
+            file,
+            pointer(&[field]),
+            Severity::Error,
+            message,
+        ));
+    };
+
+    match obj.get("trigger_time") {
+        None if obj.contains_key("schedule") => {}
+        None => error(
+            "trigger_time",
+            "缺少必填字段 trigger_time (或改用 schedule)".to_string(),
+        ),
+        Some(Value::String(s)) => {
+            if let Err(e) = NaiveDateTime::parse_from_str(s, TIME_FORMAT) {
+                error(
+                    "trigger_time",
+                    format!("时间 '{}' 不符合格式 YYYY-MM-DD HH:MM:SS: {}", s, e),
+                );
+            }
+        }
+        Some(_) => error("trigger_time", "trigger_time 必须是字符串".to_string()),
+    }
+
+    match obj.get("timezone") {
+        None => {}
+        Some(Value::String(s)) if s.parse::<Tz>().is_err() => {
+            error("timezone", format!("未知的 IANA 时区: {}", s))
+        }
+        Some(Value::String(_)) => {}
+        Some(_) => error("timezone", "timezone 必须是字符串".to_string()),
+    }
+
+    match obj.get("webhook_url") {
+        None => error("webhook_url", "缺少必填字段 webhook_url".to_string()),
+        Some(Value::String(s)) => match reqwest::Url::parse(s) {
+            Ok(url) if !matches!(url.scheme(), "http" | "https") => error(
+                "webhook_url",
+                format!("仅支持 http/https 地址, 实际为 {}", url.scheme()),
+            ),
+            Ok(url) if url.host_str().is_none() => {
+                error("webhook_url", format!("URL 缺少主机名: {}", s))
+            }
+            Ok(_) => {}
+            Err(e) => error("webhook_url", format!("不是合法的绝对 URL '{}': {}", s, e)),
+        },
+        Some(_) => error("webhook_url", "webhook_url 必须是字符串".to_string()),
+    }
+
+    match obj.get("method") {
+        None => {}
+        Some(Value::String(s)) if reqwest::Method::from_bytes(s.as_bytes()).is_err() => {
+            error("method", format!("不是合法的 HTTP 方法: {}", s))
+        }
+        Some(Value::String(_)) => {}
+        Some(_) => error("method", "method 必须是字符串".to_string()),
+    }
+
+    match obj.get("query") {
+        None => {}
+        Some(Value::Object(m)) => {
+            for (name, value) in m {
+                if value.is_array() || value.is_object() {
+                    error(
+                        "query",
+                        format!("查询参数 {} 必须是字符串、数字或布尔值", name),
+                    );
+                }
+            }
+        }
+        Some(_) => error("query", "query 必须是 JSON 对象".to_string()),
+    }
+
+    let body_type = match obj.get("body_type") {
+        None => Some(BodyType::default()),
+        Some(Value::String(s)) => match BodyType::parse(s) {
+            Ok(t) => Some(t),
+            Err(e) => {
+                error("body_type", e);
+                None
+            }
+        },
+        Some(_) => {
+            error("body_type", "body_type 必须是字符串".to_string());
+            None
+        }
+    };
+    match (obj.get("body"), body_type) {
+        (None, Some(BodyType::Text | BodyType::Raw)) => {
+            error("body", "body_type 为 text/raw 时必须提供 body".to_string())
+        }
+        (None, _) | (_, None) => {}
+        (Some(Value::Object(_)), Some(BodyType::Json)) => {}
+        (Some(_), Some(BodyType::Json)) => error("body", "body 必须是 JSON 对象".to_string()),
+        (Some(body), Some(t)) => {
+            if let Err(e) = t.check(body) {
+                error("body", e);
+            }
+        }
+    }
+
+    match obj.get("id") {
+        None | Some(Value::String(_)) => {}
+        Some(_) => error("id", "id 必须是字符串".to_string()),
+    }
+
+    match obj.get("task_name") {
+        None | Some(Value::String(_)) => {}
+        Some(_) => error("task_name", "task_name 必须是字符串".to_string()),
+    }
+
+    match obj.get("executed") {
+        None | Some(Value::Bool(_)) => {}
+        Some(_) => error("executed", "executed 必须是 true/false".to_string()),
+    }
+
+    match obj.get("executed_at") {
+        None => {}
+        Some(Value::String(s)) if NaiveDateTime::parse_from_str(s, TIME_FORMAT).is_err() => error(
+            "executed_at",
+            format!("时间 '{}' 不符合格式 YYYY-MM-DD HH:MM:SS", s),
+        ),
+        Some(Value::String(_)) => {}
+        Some(_) => error("executed_at", "executed_at 必须是字符串".to_string()),
+    }
+
+    match obj.get("dst_policy") {
+        None => {}
+        Some(Value::String(s)) if DstPolicy::parse(s).is_err() => error(
+            "dst_policy",
+            format!("未知的 dst_policy: {} (可选: earliest/latest/reject)", s),
+        ),
+        Some(Value::String(_)) => {}
+        Some(_) => error("dst_policy", "dst_policy 必须是字符串".to_string()),
+    }
+
+    match obj.get("misfire_policy") {
+        None => {}
+        Some(Value::String(s)) if matches!(s.as_str(), "skip" | "fire_once" | "fire_all") => {}
+        Some(Value::Object(m)) if m.len() == 1 && m.contains_key("fire_if_within") => {
+            match &m["fire_if_within"] {
+                Value::String(d) => {
+                    if let Err(e) = parse_duration(d) {
+                        error("misfire_policy", e);
+                    }
+                }
+                _ => error(
+                    "misfire_policy",
+                    "fire_if_within 必须是时长字符串, 如 \"6h\"".to_string(),
+                ),
+            }
+        }
+        Some(_) => error(
+            "misfire_policy",
+            "misfire_policy 可选: skip/fire_once/fire_all 或 {\"fire_if_within\": \"6h\"}"
+                .to_string(),
+        ),
+    }
+
+    if let Some(retry) = obj.get("retry") {
+        let policy = serde_json::from_value::<RetryPolicy>(retry.clone())
+            .map_err(|e| e.to_string())
+            .and_then(|p| p.check());
+        if let Err(e) = policy {
+            error("retry", format!("retry 配置无效: {}", e));
+        }
+    }
+
+    match obj.get("headers") {
+        None => {}
+        Some(Value::Object(m)) => {
+            for (name, value) in m {
+                if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                    error("headers", format!("不合法的请求头名称: {}", name));
+                } else if !value.is_string() {
+                    error("headers", format!("请求头 {} 的值必须是字符串", name));
+                }
+            }
+        }
+        Some(_) => error("headers", "headers 必须是 JSON 对象".to_string()),
+    }
+
+    if let Some(auth) = obj.get("auth") {
+        if let Err(e) = serde_json::from_value::<Auth>(auth.clone()) {
+            error(
+                "auth",
+                format!("auth 配置无效 (type 可选: bearer/basic/api_key): {}", e),
+            );
+        }
+    }
+
+    if let Some(expect) = obj.get("expect") {
+        let expect = serde_json::from_value::<Expect>(expect.clone())
+            .map_err(|e| e.to_string())
+            .and_then(|e| e.check_config());
+        if let Err(e) = expect {
+            error("expect", format!("expect 配置无效: {}", e));
+        }
+    }
+
+    match obj.get("executed_occurrences") {
+        None => {}
+        Some(Value::Array(items)) if items.iter().all(Value::is_string) => {}
+        Some(_) => error(
+            "executed_occurrences",
+            "executed_occurrences 必须是时间字符串数组".to_string(),
+        ),
+    }
+
+    if let Some(schedule) = obj.get("schedule") {
+        validate_schedule(file, schedule, &mut out);
+    }
+    // 逐项检查都通过时, 再确认运行器确实能读取该配置
+    if !out.iter().any(Diagnostic::is_error) {
+        out.extend(check_loads(file, value));
+    }
+    warn_suspicious(file, obj, &mut out);
+    out
+}
+
+// 按 TaskConfig 读取, 失败时给出出错字段
+fn check_loads(file: &str, value: &Value) -> Option<Diagnostic> {
+    let e = TaskConfig::parse(&value.to_string()).err()?;
+    let segments: Vec<String> = e
+        .path()
+        .iter()
+        .filter_map(|s| match s {
+            Segment::Seq { index } => Some(index.to_string()),
+            Segment::Map { key } => Some(key.clone()),
+            Segment::Enum { variant } => Some(variant.clone()),
+            Segment::Unknown => None,
+        })
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    Some(Diagnostic::new(
+        file,
+        pointer(&segments),
+        Severity::Error,
+        format!("无法读取配置: {}", e.inner()),
+    ))
+}
+
+fn validate_schedule(file: &str, schedule: &Value, out: &mut Vec<Diagnostic>) {
+    let obj = match schedule.as_object() {
+        Some(o) => o,
+        None => {
+            out.push(Diagnostic::new(
+                file,
+                pointer(&["schedule"]),
+                Severity::Error,
+                "schedule 必须是 JSON 对象".to_string(),
+            ));
+            return;
+        }
+    };
+    let mut error = |field: &str, message: String| {
+        out.push(Diagnostic::new(
+            file,
+            pointer(&["schedule", field]),
+            Severity::Error,
+            message,
+        ));
+    };
+
+    match obj.get("cron") {
+        None => error("cron", "缺少必填字段 cron".to_string()),
+        Some(Value::String(expr)) => match crate::schedule::parse_cron(expr) {
+            // 如 "0 0 30 2 *": 语法合法但日期不存在
+            Ok(cron) if cron.find_next_occurrence(&Utc::now(), false).is_err() => {
+                error("cron", format!("cron 表达式 '{}' 永远不会触发", expr))
+            }
+            Ok(_) => {}
+            Err(e) => error("cron", e),
+        },
+        Some(_) => error("cron", "cron 必须是字符串".to_string()),
+    }
+
+    match obj.get("timezone") {
+        None => {}
+        Some(Value::String(s)) if s.parse::<Tz>().is_err() => {
+            error("timezone", format!("未知的 IANA 时区: {}", s))
+        }
+        Some(Value::String(_)) => {}
+        Some(_) => error("timezone", "timezone 必须是字符串".to_string()),
+    }
+
+    for field in ["start", "end"] {
+        match obj.get(field) {
+            None => {}
+            Some(Value::String(s)) if NaiveDateTime::parse_from_str(s, TIME_FORMAT).is_err() => {
+                error(
+                    field,
+                    format!("时间 '{}' 不符合格式 YYYY-MM-DD HH:MM:SS", s),
+                )
+            }
+            Some(Value::String(_)) => {}
+            Some(_) => error(field, format!("{} 必须是字符串", field)),
+        }
+    }
+
+    match obj.get("count") {
+        None => {}
+        Some(Value::Number(n)) if n.as_u64().is_some_and(|n| n <= u32::MAX as u64) => {}
+        Some(_) => error("count", "count 必须是非负整数".to_string()),
+    }
+    if obj.contains_key("count") && !obj.contains_key("start") {
+        error("count", "设置 count 时必须同时设置 start".to_string());
+    }
+}
+
+// 不影响运行但多半是手误的情况
+fn warn_suspicious(file: &str, obj: &Map<String, Value>, out: &mut Vec<Diagnostic>) {
+    for key in obj.keys() {
+        if !KNOWN_FIELDS.contains(&key.as_str()) {
+            out.push(Diagnostic::new(
+                file,
+                pointer(&[key]),
+                Severity::Warning,
+                format!("未识别的字段 {} (将原样保留)", key),
+            ));
+        }
+    }
+
+    // 触发时间落在夏令时边界上时提醒 (dst_policy 为 reject 时会无法执行)
+    let tz = obj
+        .get("timezone")
+        .and_then(Value::as_str)
+        .unwrap_or("Asia/Shanghai")
+        .parse::<Tz>();
+    let trigger_time = obj
+        .get("trigger_time")
+        .and_then(Value::as_str)
+        .and_then(|s| NaiveDateTime::parse_from_str(s, TIME_FORMAT).ok());
+    if let (Ok(tz), Some(t)) = (tz, trigger_time) {
+        if let Err(e) = localize(tz, t, DstPolicy::Reject) {
+            out.push(Diagnostic::new(
+                file,
+                pointer(&["trigger_time"]),
+                Severity::Warning,
+                e.to_string(),
+            ));
+        }
+    }
+
+    if let Some(Value::String(method)) = obj.get("method") {
+        let upper = method.to_uppercase();
+        if !SUPPORTED_METHODS.contains(&upper.as_str())
+            && reqwest::Method::from_bytes(method.as_bytes()).is_ok()
+        {
+            out.push(Diagnostic::new(
+                file,
+                pointer(&["method"]),
+                Severity::Warning,
+                format!(
+                    "非标准的 HTTP 方法: {} (标准方法: {})",
+                    upper,
+                    SUPPORTED_METHODS.join("/")
+                ),
+            ));
+        }
+    }
+
+    // 密钥应通过 ${NAME} 引用环境变量, 而不是明文提交到仓库
+    if let Some(Ok(auth)) = obj
+        .get("auth")
+        .map(|a| serde_json::from_value::<Auth>(a.clone()))
+    {
+        for (field, value) in auth.secrets() {
+            if !value.is_empty() && !is_env_ref(value) {
+                out.push(Diagnostic::new(
+                    file,
+                    pointer(&["auth", field]),
+                    Severity::Warning,
+                    format!(
+                        "auth.{} 为明文, 建议改为 \"${{NAME}}\" 并在 Secrets 中设置",
+                        field
+                    ),
+                ));
+            }
+        }
+    }
+    if let Some(Value::Object(headers)) = obj.get("headers") {
+        for (name, value) in headers {
+            let sensitive = matches!(
+                name.to_lowercase().as_str(),
+                "authorization" | "proxy-authorization" | "cookie" | "x-api-key"
+            );
+            if sensitive && value.as_str().is_some_and(|v| !is_env_ref(v)) {
+                out.push(Diagnostic::new(
+                    file,
+                    pointer(&["headers", name]),
+                    Severity::Warning,
+                    format!(
+                        "请求头 {} 为明文, 建议改为 \"${{NAME}}\" 引用环境变量",
+                        name
+                    ),
+                ));
+            }
+        }
+    }
+
+    if let Some(Value::String(name)) = obj.get("task_name") {
+        let stem = Path::new(file).file_stem().and_then(|s| s.to_str());
+        if let Some(stem) = stem {
+            if stem != name {
+                out.push(Diagnostic::new(
+                    file,
+                    pointer(&["task_name"]),
+                    Severity::Warning,
+                    format!("task_name '{}' 与文件名 '{}' 不一致", name, stem),
+                ));
+            }
+        }
+    }
+}
+
+/// 校验目录下 list_configs 返回的所有文件, 并检查 task_name 是否重复
+pub fn validate_directory(dir: &str) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+    let mut names: HashMap<String, String> = HashMap::new();
+    for file in crate::scan_configs(dir) {
+        let value = match load_value(&file) {
+            Ok(v) => v,
+            Err(d) => {
+                out.push(d);
+                continue;
+            }
+        };
+        out.extend(validate_value(&file, &value));
+
+        if let Some(name) = value.get("task_name").and_then(Value::as_str) {
+            if let Some(first) = names.get(name) {
+                out.push(Diagnostic::new(
+                    &file,
+                    pointer(&["task_name"]),
+                    Severity::Warning,
+                    format!("task_name '{}' 与 {} 重复", name, first),
+                ));
+            } else {
+                names.insert(name.to_string(), file.clone());
+            }
+        }
+    }
+    out
+}
+
+// 校验单个配置文件, 返回诊断列表 (为空表示通过)
+#[pyfunction]
+pub fn validate_config(path: String, py: Python) -> Vec<Diagnostic> {
+    py.allow_threads(|| validate_file(&path))
+}
+
+// 校验目录下所有配置文件
+#[pyfunction]
+pub fn validate_dir(dir: String, py: Python) -> Vec<Diagnostic> {
+    py.allow_threads(|| validate_directory(&dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // 校验通过的配置必须能被 TaskConfig 读取
+    fn assert_loads(value: Value) -> TaskConfig {
+        let diagnostics = validate_value("task.json", &value);
+        assert!(
+            !diagnostics.iter().any(Diagnostic::is_error),
+            "{:?}",
+            diagnostics
+        );
+        TaskConfig::parse(&value.to_string()).unwrap()
+    }
+
+    fn base() -> Value {
+        json!({
+            "trigger_time": "2024-01-01 09:00:00",
+            "webhook_url": "https://example.com/hook",
+        })
+    }
+
+    // 在 base() 上覆盖字段
+    fn with(fields: Value) -> Value {
+        let mut value = base();
+        for (k, v) in fields.as_object().unwrap() {
+            value[k] = v.clone();
+        }
+        value
+    }
+
+    fn diagnostics(value: &Value, severity: Severity) -> Vec<(String, String)> {
+        validate_value("task.json", value)
+            .into_iter()
+            .filter(|d| d.severity == severity)
+            .map(|d| (d.pointer, d.message))
+            .collect()
+    }
+
+    // 恰好产生一条 error, 位于 pointer, 说明包含 needle
+    fn assert_error(value: Value, pointer: &str, needle: &str) {
+        let errors = diagnostics(&value, Severity::Error);
+        assert_eq!(errors.len(), 1, "{}: {:?}", value, errors);
+        assert_eq!(errors[0].0, pointer, "{}", value);
+        assert!(errors[0].1.contains(needle), "{}: {}", value, errors[0].1);
+    }
+
+    fn warning_pointers(file: &str, value: &Value) -> Vec<String> {
+        validate_value(file, value)
+            .into_iter()
+            .filter(|d| !d.is_error())
+            .map(|d| d.pointer)
+            .collect()
+    }
+
+    #[test]
+    fn minimal_config_is_valid() {
+        assert!(validate_value("task.json", &base()).is_empty());
+        let scheduled = json!({
+            "schedule": {"cron": "0 9 * * 1"},
+            "webhook_url": "https://example.com/hook",
+        });
+        assert!(validate_value("task.json", &scheduled).is_empty());
+    }
+
+    #[test]
+    fn rejects_non_object() {
+        let errors = diagnostics(&json!([1]), Severity::Error);
+        assert_eq!(
+            errors,
+            [(String::new(), "配置必须是 JSON 对象".to_string())]
+        );
+    }
+
+    #[test]
+    fn required_fields() {
+        assert_error(
+            json!({"webhook_url": "https://example.com"}),
+            "/trigger_time",
+            "缺少必填字段 trigger_time",
+        );
+        assert_error(
+            json!({"trigger_time": "2024-01-01 09:00:00"}),
+            "/webhook_url",
+            "缺少必填字段 webhook_url",
+        );
+    }
+
+    #[test]
+    fn field_errors() {
+        let cases = [
+            (
+                json!({"trigger_time": "2024-01-01T09:00"}),
+                "/trigger_time",
+                "不符合格式",
+            ),
+            (json!({"trigger_time": 1}), "/trigger_time", "必须是字符串"),
+            (
+                json!({"timezone": "Mars/Base"}),
+                "/timezone",
+                "未知的 IANA 时区",
+            ),
+            (json!({"timezone": 8}), "/timezone", "必须是字符串"),
+            (
+                json!({"webhook_url": "ftp://example.com"}),
+                "/webhook_url",
+                "仅支持 http/https",
+            ),
+            (
+                json!({"webhook_url": "/hook"}),
+                "/webhook_url",
+                "不是合法的绝对 URL",
+            ),
+            (json!({"webhook_url": 1}), "/webhook_url", "必须是字符串"),
+            (json!({"method": "GE T"}), "/method", "不是合法的 HTTP 方法"),
+            (json!({"method": 1}), "/method", "必须是字符串"),
+            (json!({"query": {"a": [1]}}), "/query", "查询参数 a"),
+            (json!({"query": "a=1"}), "/query", "必须是 JSON 对象"),
+            (
+                json!({"body_type": "xml"}),
+                "/body_type",
+                "未知的 body_type",
+            ),
+            (json!({"body_type": 1}), "/body_type", "必须是字符串"),
+            (json!({"body_type": "text"}), "/body", "必须提供 body"),
+            (json!({"body": [1]}), "/body", "必须是 JSON 对象"),
+            (json!({"body_type": "form", "body": "a=1"}), "/body", ""),
+            (json!({"id": 1}), "/id", "必须是字符串"),
+            (json!({"task_name": 1}), "/task_name", "必须是字符串"),
+            (json!({"executed": "yes"}), "/executed", "必须是 true/false"),
+            (
+                json!({"executed_at": "2024-01-01"}),
+                "/executed_at",
+                "不符合格式",
+            ),
+            (json!({"executed_at": 1}), "/executed_at", "必须是字符串"),
+            (
+                json!({"dst_policy": "first"}),
+                "/dst_policy",
+                "未知的 dst_policy",
+            ),
+            (json!({"dst_policy": 1}), "/dst_policy", "必须是字符串"),
+            (
+                json!({"misfire_policy": "always"}),
+                "/misfire_policy",
+                "可选",
+            ),
+            (
+                json!({"misfire_policy": {"fire_if_within": "6x"}}),
+                "/misfire_policy",
+                "",
+            ),
+            (
+                json!({"misfire_policy": {"fire_if_within": 6}}),
+                "/misfire_policy",
+                "时长字符串",
+            ),
+            (
+                json!({"retry": {"max_attempts": -1}}),
+                "/retry",
+                "retry 配置无效",
+            ),
+            (
+                json!({"headers": {"a b": "1"}}),
+                "/headers",
+                "不合法的请求头名称",
+            ),
+            (json!({"headers": {"x-a": 1}}), "/headers", "必须是字符串"),
+            (json!({"headers": []}), "/headers", "必须是 JSON 对象"),
+            (
+                json!({"auth": {"type": "digest"}}),
+                "/auth",
+                "auth 配置无效",
+            ),
+            (
+                json!({"expect": {"unknown": 1}}),
+                "/expect",
+                "expect 配置无效",
+            ),
+            (
+                json!({"executed_occurrences": [1]}),
+                "/executed_occurrences",
+                "时间字符串数组",
+            ),
+        ];
+        for (fields, pointer, needle) in cases {
+            assert_error(with(fields), pointer, needle);
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_range_status_codes() {
+        assert_error(
+            with(json!({"retry": {"retry_on": [70000]}})),
+            "/retry",
+            "状态码",
+        );
+        assert_error(
+            with(json!({"retry": {"retry_on": [99]}})),
+            "/retry",
+            "状态码",
+        );
+        assert_error(
+            with(json!({"expect": {"status": [65736]}})),
+            "/expect",
+            "状态码",
+        );
+        assert_loads(with(json!({"retry": {"retry_on": [503, "4xx"]}})));
+    }
+
+    #[test]
+    fn schedule_errors() {
+        let cases = [
+            (json!("0 9 * * 1"), "/schedule", "必须是 JSON 对象"),
+            (json!({}), "/schedule/cron", "缺少必填字段 cron"),
+            (
+                json!({"cron": "0 9 * *"}),
+                "/schedule/cron",
+                "cron 表达式无效",
+            ),
+            (
+                json!({"cron": "0 0 30 2 *"}),
+                "/schedule/cron",
+                "永远不会触发",
+            ),
+            (json!({"cron": 1}), "/schedule/cron", "必须是字符串"),
+            (
+                json!({"cron": "0 9 * * 1", "timezone": "Mars/Base"}),
+                "/schedule/timezone",
+                "未知的 IANA 时区",
+            ),
+            (
+                json!({"cron": "0 9 * * 1", "start": "2024-01-01"}),
+                "/schedule/start",
+                "不符合格式",
+            ),
+            (
+                json!({"cron": "0 9 * * 1", "end": 1}),
+                "/schedule/end",
+                "必须是字符串",
+            ),
+            (
+                json!({"cron": "0 9 * * 1", "start": "2024-01-01 00:00:00", "count": -1}),
+                "/schedule/count",
+                "非负整数",
+            ),
+            (
+                json!({"cron": "0 9 * * 1", "count": 3}),
+                "/schedule/count",
+                "必须同时设置 start",
+            ),
+        ];
+        for (schedule, pointer, needle) in cases {
+            assert_error(with(json!({"schedule": schedule})), pointer, needle);
+        }
+        // 闰日每四年触发一次, 是合法的
+        assert_loads(with(json!({"schedule": {"cron": "0 0 29 2 *"}})));
+    }
+
+    #[test]
+    fn warnings() {
+        let cases = [
+            (json!({"note": "x"}), "/note"),
+            (
+                json!({"timezone": "America/New_York", "trigger_time": "2024-03-10 02:30:00"}),
+                "/trigger_time",
+            ),
+            (json!({"method": "PURGE"}), "/method"),
+            (
+                json!({"auth": {"type": "bearer", "token": "abc"}}),
+                "/auth/token",
+            ),
+            (
+                json!({"headers": {"Authorization": "Bearer abc"}}),
+                "/headers/Authorization",
+            ),
+            (json!({"task_name": "other"}), "/task_name"),
+        ];
+        for (fields, pointer) in cases {
+            let value = with(fields);
+            assert_eq!(
+                warning_pointers("configs/task.json", &value),
+                [pointer],
+                "{}",
+                value
+            );
+            assert_loads(value);
+        }
+        // 引用环境变量的密钥与匹配文件名的 task_name 不提醒
+        let value = with(json!({
+            "task_name": "task",
+            "auth": {"type": "bearer", "token": "${TOKEN}"},
+            "headers": {"Authorization": "${AUTH}"},
+        }));
+        assert!(warning_pointers("configs/task.json", &value).is_empty());
+    }
+
+    #[test]
+    fn load_failure_points_at_field() {
+        // 逐项检查会先拦下这种配置, 这里直接验证兜底检查给出的位置
+        let d = check_loads("task.json", &with(json!({"executed": "yes"}))).unwrap();
+        assert!(d.is_error());
+        assert_eq!(d.pointer, "/executed");
+        assert!(d.message.starts_with("无法读取配置"));
+        assert!(check_loads("task.json", &base()).is_none());
+    }
+
+    #[test]
+    fn directory_reports_duplicate_task_names() {
+        let dir = std::env::temp_dir().join(format!("task_io_validate_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["a", "b"] {
+            let value = with(json!({"task_name": "a"}));
+            fs::write(dir.join(format!("{}.json", name)), value.to_string()).unwrap();
+        }
+        fs::write(dir.join("c.json"), "{").unwrap();
+
+        let diagnostics = validate_directory(dir.to_str().unwrap());
+        let messages: Vec<String> = diagnostics.iter().map(|d| d.message.clone()).collect();
+        assert_eq!(diagnostics.len(), 3, "{:?}", messages);
+        assert!(messages[0].contains("与文件名 'b' 不一致"));
+        assert!(messages[1].contains("task_name 'a' 与"));
+        assert!(messages[2].starts_with("JSON 格式错误"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn accepted_body_types_load() {
+        for (name, expected) in [
+            ("JSON", BodyType::Json),
+            ("Form", BodyType::Form),
+            ("multipart", BodyType::Multipart),
+        ] {
+            let mut value = base();
+            value["body_type"] = json!(name);
+            value["body"] = json!({"a": "1"});
+            assert_eq!(assert_loads(value).body_type, expected);
+        }
+        let mut value = base();
+        value["body_type"] = json!("TEXT");
+        value["body"] = json!("hello");
+        assert_eq!(assert_loads(value).body_type, BodyType::Text);
+    }
+
+    #[test]
+    fn accepted_dst_policies_load() {
+        for (name, expected) in [
+            ("Latest", DstPolicy::Latest),
+            ("REJECT", DstPolicy::Reject),
+            ("earliest", DstPolicy::Earliest),
+        ] {
+            let mut value = base();
+            value["dst_policy"] = json!(name);
+            assert_eq!(assert_loads(value).dst_policy, expected);
+        }
+    }
+}